@@ -0,0 +1,78 @@
+//! Python-side `Markup` hot paths, driven through an embedded interpreter:
+//!
+//!     cargo bench --bench markup --no-default-features --features python
+//!
+//! With the default `extension-module` feature libpython is not linked, so
+//! the bench only prints how to run it.
+
+#[cfg(not(feature = "extension-module"))]
+mod python {
+    use criterion::{black_box, Criterion};
+    use pyo3::prelude::*;
+    use pyo3::types::{IntoPyDict, PyString};
+    use pyo3::wrap_pymodule;
+
+    // Imports the `rysafe` package from `python/`, with `rysafe._rysafe`
+    // served by this build rather than an installed wheel.
+    fn import_markup(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+        let sys = py.import_bound("sys")?;
+        sys.getattr("modules")?
+            .set_item("rysafe._rysafe", wrap_pymodule!(rysafe::_rysafe)(py))?;
+        sys.getattr("path")?.call_method1(
+            "insert",
+            (0, concat!(env!("CARGO_MANIFEST_DIR"), "/python")),
+        )?;
+        py.import_bound("rysafe")?.getattr("Markup")
+    }
+
+    pub fn bench_markup(c: &mut Criterion) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let markup_type = import_markup(py).unwrap();
+            let markup = markup_type.call1(("<b>Hello</b> ",)).unwrap();
+            let other = markup_type.call1(("<i>World</i>",)).unwrap();
+            let text = PyString::new_bound(py, "Tom & 'Jerry' <script>");
+
+            // Markup operand: copied as is via `__html__`.
+            c.bench_function("markup_add_markup", |b| {
+                b.iter(|| black_box(&markup).add(black_box(&other)).unwrap())
+            });
+            // str operand: escaped before concatenation.
+            c.bench_function("markup_add_str", |b| {
+                b.iter(|| black_box(&markup).add(black_box(&text)).unwrap())
+            });
+            c.bench_function("str_radd_markup", |b| {
+                b.iter(|| black_box(&text).add(black_box(&markup)).unwrap())
+            });
+
+            // Tuple operand: each item wrapped so `%s` inserts it escaped.
+            let template = markup_type.call1(("<p>%s: %s</p>",)).unwrap();
+            c.bench_function("markup_mod_tuple", |b| {
+                b.iter(|| {
+                    let args = (black_box(&text), black_box(&other));
+                    black_box(&template).rem(args).unwrap()
+                })
+            });
+            // Mapping operand, for `%(name)s` fields.
+            let template = markup_type.call1(("<p>%(name)s</p>",)).unwrap();
+            c.bench_function("markup_mod_mapping", |b| {
+                b.iter(|| {
+                    let kwargs = [("name", black_box(&text))].into_py_dict_bound(py);
+                    black_box(&template).rem(kwargs).unwrap()
+                })
+            });
+        });
+    }
+}
+
+#[cfg(not(feature = "extension-module"))]
+fn main() {
+    let mut criterion = criterion::Criterion::default().configure_from_args();
+    python::bench_markup(&mut criterion);
+    criterion.final_summary();
+}
+
+#[cfg(feature = "extension-module")]
+fn main() {
+    eprintln!("markup bench: rerun with --no-default-features --features python");
+}
@@ -1,49 +1,105 @@
-use std::borrow::Cow;
-
-const ESCAPED_CHARS: [(char, &str); 5] = [
-    ('&', "&amp;"),
-    ('<', "&lt;"),
-    ('>', "&gt;"),
-    ('"', "&#34;"),
-    ('\'', "&#39;"),
-];
-
-pub fn escape(text: &str) -> Cow<str> {
-    let mut escaped = None;
-    let mut last_end = 0;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-    for (i, ch) in text.char_indices() {
-        let replacement = match ch {
-            '&' => "&amp;",
-            '<' => "&lt;",
-            '>' => "&gt;",
-            '"' => "&#34;",
-            '\'' => "&#39;",
-            _ => continue,
-        };
+extern crate alloc;
 
-        if escaped.is_none() {
-            let mut s = String::with_capacity(text.len() + 10);
-            escaped = Some(s);
-        }
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt;
 
-        if let Some(ref mut s) = escaped {
-            s.push_str(&text[last_end..i]);
-            s.push_str(replacement);
-            last_end = i + ch.len_utf8();
-        }
+/// How [`replacement`] spells `"` and `'`. `&`, `<` and `>` are the same in
+/// every style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuoteStyle {
+    /// `&#34;` and `&#39;`, as MarkupSafe emits them. Every numeric reference
+    /// in the output is decimal, for parsers that reject `&#x`.
+    #[default]
+    Decimal,
+    /// `&#x22;` and `&#x27;`.
+    Hex,
+    /// `&quot;` and `&#x27;` (HTML 4 has no name for `'`), as Python's
+    /// `html.escape` emits them.
+    Named,
+}
+
+/// The entity for `byte` in `style`, or `None` unless `byte` is one of `&`,
+/// `<`, `>`, `"` and `'`. The HTML escapers in `rysafe` are all built on
+/// this table.
+pub const fn replacement(byte: u8, style: QuoteStyle) -> Option<&'static str> {
+    Some(match (byte, style) {
+        (b'&', _) => "&amp;",
+        (b'<', _) => "&lt;",
+        (b'>', _) => "&gt;",
+        (b'"', QuoteStyle::Decimal) => "&#34;",
+        (b'"', QuoteStyle::Hex) => "&#x22;",
+        (b'"', QuoteStyle::Named) => "&quot;",
+        (b'\'', QuoteStyle::Decimal) => "&#39;",
+        (b'\'', QuoteStyle::Hex | QuoteStyle::Named) => "&#x27;",
+        _ => return None,
+    })
+}
+
+/// Returns true if `text` contains any of `&`, `<`, `>`, `"` and `'`, that is,
+/// if [`escape`] cannot borrow it.
+pub fn needs_escaping(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    // Two SIMD searches rule out safe input far faster than a byte-wise walk.
+    memchr::memchr3(b'&', b'<', b'>', bytes).is_some()
+        || memchr::memchr2(b'"', b'\'', bytes).is_some()
+}
+
+/// Exact byte length of `text` with every special replaced by its `style`
+/// entity, computed in one scan without allocating. Usable in const contexts.
+pub const fn escaped_len(text: &str, style: QuoteStyle) -> usize {
+    let bytes = text.as_bytes();
+    let mut len = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        // Saturating, so an impossible size fails in the allocator instead of
+        // wrapping to a too-small buffer.
+        len = len.saturating_add(match replacement(bytes[i], style) {
+            Some(entity) => entity.len(),
+            None => 1,
+        });
+        i += 1;
     }
+    len
+}
 
-    match escaped {
-        Some(mut s) => {
-            s.push_str(&text[last_end..]);
-            Cow::Owned(s)
-        }
-        None => Cow::Borrowed(text),
+/// Escapes `&`, `<`, `>`, `"` and `'`, borrowing `text` when none occur. This
+/// is the implementation behind `rysafe::escape_html`.
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !needs_escaping(text) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(escaped_len(text, QuoteStyle::Decimal));
+    // Writing into a String cannot fail.
+    let _ = escape_to(text, &mut escaped);
+    Cow::Owned(escaped)
+}
+
+/// Writes the output of [`escape`] into `out`: each run of safe text and each
+/// entity is one `write_str`, and safe input is forwarded whole.
+pub fn escape_to<W: fmt::Write>(text: &str, out: &mut W) -> fmt::Result {
+    if !needs_escaping(text) {
+        return out.write_str(text);
     }
+    let mut last_end = 0;
+
+    // The specials are ASCII and never occur inside a multibyte sequence, so
+    // every slice boundary below is a char boundary.
+    for (i, &byte) in text.as_bytes().iter().enumerate() {
+        let Some(replacement) = replacement(byte, QuoteStyle::Decimal) else {
+            continue;
+        };
+        out.write_str(&text[last_end..i])?;
+        out.write_str(replacement)?;
+        last_end = i + 1;
+    }
+
+    out.write_str(&text[last_end..])
 }
 
-pub fn escape_silent(text: Option<&str>) -> Cow<str> {
+pub fn escape_silent(text: Option<&str>) -> Cow<'_, str> {
     match text {
         Some(t) => escape(t),
         None => Cow::Borrowed(""),
@@ -81,6 +137,59 @@ mod tests {
         assert_eq!(escape_silent(Some("<test>")), "&lt;test&gt;");
     }
 
+    #[test]
+    fn test_replacement_styles() {
+        assert_eq!(replacement(b'&', QuoteStyle::Named), Some("&amp;"));
+        assert_eq!(replacement(b'"', QuoteStyle::Decimal), Some("&#34;"));
+        assert_eq!(replacement(b'"', QuoteStyle::Hex), Some("&#x22;"));
+        assert_eq!(replacement(b'"', QuoteStyle::Named), Some("&quot;"));
+        assert_eq!(replacement(b'\'', QuoteStyle::Named), Some("&#x27;"));
+        assert_eq!(replacement(b'/', QuoteStyle::Hex), None);
+        assert_eq!(replacement(0xC3, QuoteStyle::Decimal), None);
+    }
+
+    #[test]
+    fn test_needs_escaping() {
+        for special in ["&", "<", ">", "\"", "'"] {
+            assert!(needs_escaping(special));
+            assert!(needs_escaping(
+                &["long safe prefix 日本 ", special].concat()
+            ));
+        }
+        assert!(!needs_escaping(""));
+        assert!(!needs_escaping("plain / text 日本"));
+    }
+
+    #[test]
+    fn test_escaped_len() {
+        let input = "a < b & \"c\" 日本";
+        assert_eq!(escaped_len(input, QuoteStyle::Decimal), escape(input).len());
+        assert_eq!(
+            escaped_len(input, QuoteStyle::Named),
+            escape(input).len() + 2
+        );
+        assert_eq!(escaped_len("", QuoteStyle::Hex), 0);
+        assert_eq!(escaped_len("日本", QuoteStyle::Hex), "日本".len());
+    }
+
+    #[test]
+    fn test_escape_to() {
+        for input in ["", "plain 日本", "a < b & 'c'", "\"\""] {
+            let mut out = String::from("> ");
+            escape_to(input, &mut out).unwrap();
+            assert_eq!(out, ["> ", &escape(input)].concat());
+        }
+    }
+
+    #[test]
+    fn test_exact_capacity() {
+        match escape("a < b & 'c' 日本") {
+            Cow::Owned(s) => assert_eq!(s.capacity(), s.len()),
+            Cow::Borrowed(_) => panic!("input needs escaping"),
+        }
+        assert!(matches!(escape("plain 日本"), Cow::Borrowed(_)));
+    }
+
     #[test]
     fn test_unicode() {
         assert_eq!(escape("Hello 世界 <test>"), "Hello 世界 &lt;test&gt;");
@@ -58,15 +58,17 @@ fn test_attribute_injection() {
 
 proptest! {
     #[test]
-    fn test_escape_idempotent(s: String) {
+    fn test_escape_twice_reescapes_ampersands(s: String) {
         let once = escape(&s);
         let twice = escape(&once);
-        prop_assert_eq!(&once, &twice);
+        // Escaping is not idempotent: a second pass re-escapes the `&` that
+        // starts every entity, and changes nothing else.
+        prop_assert_eq!(twice.as_ref(), once.replace('&', "&amp;"));
     }
 
     #[test]
     fn test_escape_preserves_safe_chars(s in "[a-zA-Z0-9 ]+") {
-        prop_assert_eq!(escape(&s).as_ref(), &s);
+        prop_assert_eq!(escape(&s), s.as_str());
     }
 
     #[test]
@@ -80,7 +82,12 @@ proptest! {
         let escaped = escape(&s);
         prop_assert!(!escaped.contains('<') || s.contains('<'));
         prop_assert!(!escaped.contains('>') || s.contains('>'));
-        prop_assert!(!escaped.contains('&') || escaped.contains("&amp;"));
+        // Every `&` left in the output must start one of the entities, which
+        // "contains &amp; somewhere" does not establish.
+        let stripped = ["&amp;", "&lt;", "&gt;", "&#34;", "&#39;"]
+            .iter()
+            .fold(escaped.to_string(), |acc, entity| acc.replace(entity, ""));
+        prop_assert!(!stripped.contains('&'));
         prop_assert!(!escaped.contains('"') || s.contains('"'));
         prop_assert!(!escaped.contains('\'') || s.contains('\''));
     }
@@ -0,0 +1,269 @@
+from __future__ import annotations
+
+import string
+import typing as t
+
+from ._rysafe import _escape_inner
+from ._rysafe import _strip_tags
+from ._rysafe import escape_bytes
+from ._rysafe import markup_memory_bytes
+from ._rysafe import unescape
+from ._rysafe import unescape_bytes
+
+__all__ = [
+    "Markup",
+    "escape",
+    "escape_bytes",
+    "escape_silent",
+    "markup_memory_bytes",
+    "soft_str",
+    "unescape",
+    "unescape_bytes",
+]
+
+
+def escape(s: t.Any, /) -> Markup:
+    """Escapes ``&``, ``<``, ``>``, ``"`` and ``'`` in ``str(s)``, returning
+    ``Markup``. Objects with ``__html__`` are trusted as they are. Unlike
+    MarkupSafe, ``bytes`` and ``bytearray`` are decoded as UTF-8 first rather
+    than escaped as their ``repr``.
+    """
+    # type() rather than isinstance(), so a proxy claiming to be a str still
+    # goes through str() below.
+    if type(s) is str or isinstance(s, (bytes, bytearray)):
+        return Markup(_escape_inner(s))
+    if hasattr(s, "__html__"):
+        return Markup(s.__html__())
+    return Markup(_escape_inner(str(s)))
+
+
+def escape_silent(s: t.Any | None = None, /) -> Markup:
+    """Like :func:`escape`, but ``None`` becomes the empty ``Markup``."""
+    if s is None:
+        return Markup()
+    return escape(s)
+
+
+def soft_str(s: t.Any, /) -> str:
+    """Converts ``s`` to ``str`` unless it already is one, keeping ``Markup``
+    intact so it is not escaped again.
+    """
+    if isinstance(s, str):
+        return s
+    return str(s)
+
+
+class Markup(str):
+    """A string that is already HTML. ``Markup("<b>")`` holds ``<b>`` exactly
+    as it will be emitted; use :meth:`escape` to wrap untrusted text.
+
+    It is a ``str``, so it works anywhere one is expected. The ``str`` methods
+    escape their text arguments and return ``Markup``.
+    """
+
+    __slots__ = ()
+
+    def __new__(
+        cls, base: t.Any = "", encoding: str | None = None, errors: str = "strict"
+    ) -> Markup:
+        if hasattr(base, "__html__"):
+            base = base.__html__()
+        if encoding is None:
+            return super().__new__(cls, base)
+        return super().__new__(cls, base, encoding, errors)
+
+    @classmethod
+    def escape(cls, s: t.Any, /) -> Markup:
+        """Same as the module-level :func:`escape`, returning ``cls``."""
+        rv = escape(s)
+        if rv.__class__ is not cls:
+            return cls(rv)
+        return rv
+
+    def __html__(self, /) -> Markup:
+        return self
+
+    def __html_format__(self, format_spec: str, /) -> Markup:
+        if format_spec:
+            raise ValueError("Unsupported format specification for Markup.")
+        return self
+
+    def __repr__(self, /) -> str:
+        return f"{self.__class__.__name__}({super().__repr__()})"
+
+    def __add__(self, value: t.Any, /) -> Markup:
+        if isinstance(value, str) or hasattr(value, "__html__"):
+            return self.__class__(super().__add__(self.escape(value)))
+        return NotImplemented
+
+    def __radd__(self, value: t.Any, /) -> Markup:
+        if isinstance(value, str) or hasattr(value, "__html__"):
+            return self.escape(value).__add__(self)
+        return NotImplemented
+
+    def __mul__(self, value: t.SupportsIndex, /) -> Markup:
+        return self.__class__(super().__mul__(value))
+
+    __rmul__ = __mul__
+
+    def __mod__(self, value: t.Any, /) -> Markup:
+        if isinstance(value, tuple):
+            value = tuple(_MarkupEscapeHelper(x, self.escape) for x in value)
+        elif hasattr(type(value), "__getitem__") and not isinstance(value, str):
+            # A mapping, for `%(name)s` fields.
+            value = _MarkupEscapeHelper(value, self.escape)
+        else:
+            value = (_MarkupEscapeHelper(value, self.escape),)
+        return self.__class__(super().__mod__(value))
+
+    def __getitem__(self, key: t.SupportsIndex | slice, /) -> Markup:
+        return self.__class__(super().__getitem__(key))
+
+    def __iter__(self, /) -> t.Iterator[Markup]:
+        return map(self.__class__, super().__iter__())
+
+    def join(self, iterable: t.Iterable[t.Any], /) -> Markup:
+        return self.__class__(super().join(map(self.escape, iterable)))
+
+    def format(self, *args: t.Any, **kwargs: t.Any) -> Markup:
+        formatter = EscapeFormatter(self.escape)
+        return self.__class__(formatter.vformat(self, args, kwargs))
+
+    def format_map(self, mapping: t.Mapping[str, t.Any], /) -> Markup:
+        formatter = EscapeFormatter(self.escape)
+        return self.__class__(formatter.vformat(self, (), mapping))
+
+    def unescape(self, /) -> str:
+        """Decodes character references, returning a plain ``str``."""
+        return unescape(str(self))
+
+    def striptags(self, /) -> str:
+        """Drops comments and tags, collapses whitespace and unescapes."""
+        return _strip_tags(str(self))
+
+    def capitalize(self, /) -> Markup:
+        return self.__class__(super().capitalize())
+
+    def title(self, /) -> Markup:
+        return self.__class__(super().title())
+
+    def lower(self, /) -> Markup:
+        return self.__class__(super().lower())
+
+    def upper(self, /) -> Markup:
+        return self.__class__(super().upper())
+
+    def swapcase(self, /) -> Markup:
+        return self.__class__(super().swapcase())
+
+    def casefold(self, /) -> Markup:
+        return self.__class__(super().casefold())
+
+    def strip(self, chars: str | None = None, /) -> Markup:
+        return self.__class__(super().strip(chars))
+
+    def lstrip(self, chars: str | None = None, /) -> Markup:
+        return self.__class__(super().lstrip(chars))
+
+    def rstrip(self, chars: str | None = None, /) -> Markup:
+        return self.__class__(super().rstrip(chars))
+
+    def replace(self, old: str, new: str, count: t.SupportsIndex = -1, /) -> Markup:
+        return self.__class__(super().replace(old, self.escape(new), count))
+
+    def center(self, width: t.SupportsIndex, fillchar: str = " ", /) -> Markup:
+        return self.__class__(super().center(width, self.escape(fillchar)))
+
+    def ljust(self, width: t.SupportsIndex, fillchar: str = " ", /) -> Markup:
+        return self.__class__(super().ljust(width, self.escape(fillchar)))
+
+    def rjust(self, width: t.SupportsIndex, fillchar: str = " ", /) -> Markup:
+        return self.__class__(super().rjust(width, self.escape(fillchar)))
+
+    def zfill(self, width: t.SupportsIndex, /) -> Markup:
+        return self.__class__(super().zfill(width))
+
+    def expandtabs(self, /, tabsize: t.SupportsIndex = 8) -> Markup:
+        return self.__class__(super().expandtabs(tabsize))
+
+    def translate(self, table: t.Any, /) -> Markup:
+        return self.__class__(super().translate(table))
+
+    def removeprefix(self, prefix: str, /) -> Markup:
+        return self.__class__(super().removeprefix(prefix))
+
+    def removesuffix(self, suffix: str, /) -> Markup:
+        return self.__class__(super().removesuffix(suffix))
+
+    def split(  # type: ignore[override]
+        self, /, sep: str | None = None, maxsplit: t.SupportsIndex = -1
+    ) -> list[Markup]:
+        return [self.__class__(v) for v in super().split(sep, maxsplit)]
+
+    def rsplit(  # type: ignore[override]
+        self, /, sep: str | None = None, maxsplit: t.SupportsIndex = -1
+    ) -> list[Markup]:
+        return [self.__class__(v) for v in super().rsplit(sep, maxsplit)]
+
+    def splitlines(self, /, keepends: bool = False) -> list[Markup]:  # type: ignore[override]
+        return [self.__class__(v) for v in super().splitlines(keepends)]
+
+    def partition(self, sep: str, /) -> tuple[Markup, Markup, Markup]:
+        return tuple(map(self.__class__, super().partition(sep)))  # type: ignore[return-value]
+
+    def rpartition(self, sep: str, /) -> tuple[Markup, Markup, Markup]:
+        return tuple(map(self.__class__, super().rpartition(sep)))  # type: ignore[return-value]
+
+
+class EscapeFormatter(string.Formatter):
+    """Drives :meth:`Markup.format`, escaping every substituted value that is
+    not already markup.
+    """
+
+    __slots__ = ("escape",)
+
+    def __init__(self, escape: t.Callable[[t.Any], Markup]) -> None:
+        self.escape = escape
+        super().__init__()
+
+    def format_field(self, value: t.Any, format_spec: str) -> str:
+        if hasattr(value, "__html_format__"):
+            rv = value.__html_format__(format_spec)
+        elif hasattr(value, "__html__"):
+            if format_spec:
+                raise ValueError(
+                    f"Format specifier {format_spec} given, but {type(value).__name__}"
+                    " does not define __html_format__."
+                )
+            # Markup so the escape below trusts it; MarkupSafe escapes it again.
+            rv = Markup(value.__html__())
+        else:
+            rv = super().format_field(value, str(format_spec))
+        return str(self.escape(rv))
+
+
+class _MarkupEscapeHelper:
+    """Wraps a :meth:`Markup.__mod__` operand so that ``%s`` and ``%r``
+    insert it escaped, while numeric conversions still see the raw value.
+    """
+
+    __slots__ = ("obj", "escape")
+
+    def __init__(self, obj: t.Any, escape: t.Callable[[t.Any], Markup]) -> None:
+        self.obj = obj
+        self.escape = escape
+
+    def __getitem__(self, key: t.Any, /) -> _MarkupEscapeHelper:
+        return self.__class__(self.obj[key], self.escape)
+
+    def __str__(self, /) -> str:
+        return str(self.escape(self.obj))
+
+    def __repr__(self, /) -> str:
+        return str(self.escape(repr(self.obj)))
+
+    def __int__(self, /) -> int:
+        return int(self.obj)
+
+    def __float__(self, /) -> float:
+        return float(self.obj)
@@ -0,0 +1,151 @@
+//! C ABI exports (with the `capi` feature).
+//!
+//! Ownership rules:
+//!
+//! - Input is borrowed for the duration of the call only: `len` bytes of
+//!   UTF-8 at `ptr`, no terminator needed. NUL bytes are rejected, as in
+//!   [`validate_utf8`].
+//! - Every non-null result is a fresh NUL-terminated buffer owned by the
+//!   caller, which must release it with [`rysafe_free`] and nothing else.
+//! - Null is returned when `ptr` is null or the input is not valid UTF-8 or
+//!   contains NUL. `*out_len` is then left untouched.
+//! - `out_len` may be null; otherwise it receives the result's length,
+//!   excluding the terminator.
+
+use std::borrow::Cow;
+use std::ffi::{c_char, CString};
+use std::{ptr, slice};
+
+use crate::error::validate_utf8;
+use crate::escape::{escape_html, unescape_html};
+
+unsafe fn convert(
+    input: *const c_char,
+    len: usize,
+    out_len: *mut usize,
+    f: fn(&str) -> Cow<'_, str>,
+) -> *mut c_char {
+    if input.is_null() {
+        return ptr::null_mut();
+    }
+    let bytes = slice::from_raw_parts(input.cast::<u8>(), len);
+    let Ok(text) = validate_utf8(bytes) else {
+        return ptr::null_mut();
+    };
+    let Ok(output) = CString::new(f(text).into_owned()) else {
+        return ptr::null_mut();
+    };
+    if !out_len.is_null() {
+        *out_len = output.as_bytes().len();
+    }
+    output.into_raw()
+}
+
+/// [`escape_html`] over a C buffer.
+///
+/// # Safety
+///
+/// `ptr` must be null or valid for reads of `len` bytes, and `out_len` must
+/// be null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn rysafe_escape_html(
+    ptr: *const c_char,
+    len: usize,
+    out_len: *mut usize,
+) -> *mut c_char {
+    convert(ptr, len, out_len, escape_html)
+}
+
+/// [`unescape_html`] over a C buffer.
+///
+/// # Safety
+///
+/// Same as [`rysafe_escape_html`].
+#[no_mangle]
+pub unsafe extern "C" fn rysafe_unescape_html(
+    ptr: *const c_char,
+    len: usize,
+    out_len: *mut usize,
+) -> *mut c_char {
+    convert(ptr, len, out_len, unescape_html)
+}
+
+/// Releases a buffer returned by this module. Null is a no-op.
+///
+/// # Safety
+///
+/// `ptr` must be null or a result of [`rysafe_escape_html`] /
+/// [`rysafe_unescape_html`] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rysafe_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn call(
+        f: unsafe extern "C" fn(*const c_char, usize, *mut usize) -> *mut c_char,
+        input: &[u8],
+    ) -> Option<Vec<u8>> {
+        let mut len = usize::MAX;
+        let out = f(input.as_ptr().cast(), input.len(), &mut len);
+        if out.is_null() {
+            assert_eq!(len, usize::MAX);
+            return None;
+        }
+        let bytes = slice::from_raw_parts(out.cast::<u8>(), len + 1).to_vec();
+        rysafe_free(out);
+        assert_eq!(bytes.last(), Some(&0));
+        Some(bytes[..len].to_vec())
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let text = "<b>Tom & 'Jerry'</b> 日本🔥";
+        unsafe {
+            let escaped = call(rysafe_escape_html, text.as_bytes()).unwrap();
+            assert_eq!(
+                escaped,
+                "&lt;b&gt;Tom &amp; &#39;Jerry&#39;&lt;/b&gt; 日本🔥".as_bytes()
+            );
+            let back = call(rysafe_unescape_html, &escaped).unwrap();
+            assert_eq!(back, text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_input_needs_no_terminator() {
+        let buf = b"a<b<c";
+        unsafe {
+            assert_eq!(call(rysafe_escape_html, &buf[..3]).unwrap(), b"a&lt;b");
+            assert_eq!(call(rysafe_escape_html, &buf[..0]).unwrap(), b"");
+        }
+    }
+
+    #[test]
+    fn test_rejected_input_returns_null() {
+        unsafe {
+            assert_eq!(call(rysafe_escape_html, b"\xff"), None);
+            assert_eq!(call(rysafe_escape_html, b"a\0b"), None);
+            assert_eq!(
+                call(rysafe_unescape_html, b"&#0;").unwrap(),
+                "\u{FFFD}".as_bytes()
+            );
+            assert!(rysafe_escape_html(ptr::null(), 4, ptr::null_mut()).is_null());
+        }
+    }
+
+    #[test]
+    fn test_null_out_len_and_free() {
+        unsafe {
+            let out = rysafe_escape_html(b"&".as_ptr().cast(), 1, ptr::null_mut());
+            assert!(!out.is_null());
+            rysafe_free(out);
+            rysafe_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Default ceiling for the `try_escape_html*` functions: 10 MiB.
+pub const MAX_INPUT_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscapeError {
+    InvalidUtf8,
+    NulByte { position: usize },
+    HeaderInjection { position: usize, ch: char },
+    ControlCharacter { position: usize, ch: char },
+    InputTooLarge { len: usize, max: usize },
+    ProcessingError(String),
+}
+
+pub type EscapeResult<T> = Result<T, EscapeError>;
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EscapeError::InvalidUtf8 => write!(f, "input is not valid UTF-8"),
+            EscapeError::NulByte { position } => {
+                write!(f, "NUL byte at byte {} is not allowed", position)
+            }
+            EscapeError::HeaderInjection { position, ch } => write!(
+                f,
+                "control character {:?} at byte {} is not allowed in a header value",
+                ch, position
+            ),
+            EscapeError::ControlCharacter { position, ch } => write!(
+                f,
+                "control character {:?} at byte {} is not allowed",
+                ch, position
+            ),
+            EscapeError::InputTooLarge { len, max } => {
+                write!(f, "input of {} bytes exceeds the {} byte limit", len, max)
+            }
+            EscapeError::ProcessingError(msg) => write!(f, "processing error: {}", msg),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EscapeError {}
+
+/// Accepts `bytes` only if they are UTF-8 without embedded NUL bytes, which
+/// would truncate the text on its way through C strings. The first NUL is
+/// reported as [`EscapeError::NulByte`].
+pub fn validate_utf8(bytes: &[u8]) -> EscapeResult<&str> {
+    let text = core::str::from_utf8(bytes).map_err(|_| EscapeError::InvalidUtf8)?;
+    if let Some(position) = memchr::memchr(0, bytes) {
+        return Err(EscapeError::NulByte { position });
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_validate_utf8() {
+        assert_eq!(validate_utf8(b"plain"), Ok("plain"));
+        assert_eq!(
+            validate_utf8(b"a\0b"),
+            Err(EscapeError::NulByte { position: 1 })
+        );
+        assert_eq!(
+            EscapeError::NulByte { position: 1 }.to_string(),
+            "NUL byte at byte 1 is not allowed"
+        );
+        assert_eq!(validate_utf8(b"\xff"), Err(EscapeError::InvalidUtf8));
+        assert_eq!(validate_utf8("日本".as_bytes()), Ok("日本"));
+    }
+}
@@ -0,0 +1,2883 @@
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::fmt::{self, Write as _};
+#[cfg(feature = "std")]
+use std::ffi::{OsStr, OsString};
+#[cfg(feature = "std")]
+use std::io;
+
+pub use rysafe_core::QuoteStyle;
+
+use crate::entities;
+use crate::error::{validate_utf8, EscapeError, EscapeResult, MAX_INPUT_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderPolicy {
+    #[default]
+    Reject,
+    Strip,
+}
+
+fn is_header_control(ch: char) -> bool {
+    ch.is_ascii_control() && ch != '\t'
+}
+
+/// Checks a value destined for an HTTP header, not an HTML document.
+///
+/// CR, LF and every other ASCII control character except horizontal tab are
+/// rejected, which prevents header injection and response splitting.
+pub fn escape_header_value(input: &str) -> EscapeResult<Cow<'_, str>> {
+    escape_header_value_with(input, HeaderPolicy::Reject)
+}
+
+/// Like [`escape_header_value`], but `HeaderPolicy::Strip` drops offending
+/// characters instead of returning an error.
+pub fn escape_header_value_with(input: &str, policy: HeaderPolicy) -> EscapeResult<Cow<'_, str>> {
+    let first = match input.char_indices().find(|&(_, ch)| is_header_control(ch)) {
+        Some(found) => found,
+        None => return Ok(Cow::Borrowed(input)),
+    };
+
+    match policy {
+        HeaderPolicy::Reject => Err(EscapeError::HeaderInjection {
+            position: first.0,
+            ch: first.1,
+        }),
+        HeaderPolicy::Strip => Ok(Cow::Owned(
+            input.chars().filter(|&ch| !is_header_control(ch)).collect(),
+        )),
+    }
+}
+
+// Every character the escapers replace is ASCII, and ASCII bytes never occur
+// inside a multibyte UTF-8 sequence, so the scan runs over bytes without
+// decoding and every split lands on a char boundary. `replacement` is only
+// consulted for ASCII.
+fn ascii_replacement(
+    byte: u8,
+    replacement: &impl Fn(char) -> Option<&'static str>,
+) -> Option<&'static str> {
+    if byte.is_ascii() {
+        replacement(byte as char)
+    } else {
+        None
+    }
+}
+
+fn escape_with_to<W: fmt::Write>(
+    input: &str,
+    out: &mut W,
+    replacement: impl Fn(char) -> Option<&'static str>,
+) -> fmt::Result {
+    let mut last_end = 0;
+
+    for (i, &byte) in input.as_bytes().iter().enumerate() {
+        let Some(entity) = ascii_replacement(byte, &replacement) else {
+            continue;
+        };
+        out.write_str(&input[last_end..i])?;
+        out.write_str(entity)?;
+        last_end = i + 1;
+    }
+
+    out.write_str(&input[last_end..])
+}
+
+fn escape_with<'a>(
+    input: &'a str,
+    replacement: impl Fn(char) -> Option<&'static str>,
+) -> Cow<'a, str> {
+    let Some(first) = input
+        .bytes()
+        .position(|byte| ascii_replacement(byte, &replacement).is_some())
+    else {
+        return Cow::Borrowed(input);
+    };
+
+    let mut escaped = String::with_capacity(input.len() + 16);
+    escaped.push_str(&input[..first]);
+    // Writing into a String cannot fail.
+    let _ = escape_with_to(&input[first..], &mut escaped, replacement);
+    Cow::Owned(escaped)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlPolicy {
+    /// Copies control characters through unchanged, as [`escape_html`] does.
+    #[default]
+    Pass,
+    /// Rejects input containing one; see [`try_escape_html_with`].
+    Error,
+    /// Replaces each with a numeric reference such as `&#1;`.
+    Encode,
+}
+
+// ASCII controls other than tab, LF and CR, which XML 1.1 only allows as
+// references and XML 1.0 not at all.
+fn is_disallowed_control(ch: char) -> bool {
+    ch.is_ascii_control() && !matches!(ch, '\t' | '\n' | '\r')
+}
+
+const CONTROL_DECIMAL: [&str; 32] = [
+    "&#0;", "&#1;", "&#2;", "&#3;", "&#4;", "&#5;", "&#6;", "&#7;", "&#8;", "&#9;", "&#10;",
+    "&#11;", "&#12;", "&#13;", "&#14;", "&#15;", "&#16;", "&#17;", "&#18;", "&#19;", "&#20;",
+    "&#21;", "&#22;", "&#23;", "&#24;", "&#25;", "&#26;", "&#27;", "&#28;", "&#29;", "&#30;",
+    "&#31;",
+];
+
+const CONTROL_HEX: [&str; 32] = [
+    "&#x0;", "&#x1;", "&#x2;", "&#x3;", "&#x4;", "&#x5;", "&#x6;", "&#x7;", "&#x8;", "&#x9;",
+    "&#xA;", "&#xB;", "&#xC;", "&#xD;", "&#xE;", "&#xF;", "&#x10;", "&#x11;", "&#x12;", "&#x13;",
+    "&#x14;", "&#x15;", "&#x16;", "&#x17;", "&#x18;", "&#x19;", "&#x1A;", "&#x1B;", "&#x1C;",
+    "&#x1D;", "&#x1E;", "&#x1F;",
+];
+
+/// Tunes which characters [`escape_html_with`] replaces and how.
+///
+/// `EscapeOptions::default()` reproduces [`escape_html`] exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeOptions {
+    quote_style: QuoteStyle,
+    escape_gt: bool,
+    escape_slash: bool,
+    control_chars: ControlPolicy,
+}
+
+impl Default for EscapeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EscapeOptions {
+    pub const fn new() -> Self {
+        EscapeOptions {
+            quote_style: QuoteStyle::Decimal,
+            escape_gt: true,
+            escape_slash: false,
+            control_chars: ControlPolicy::Pass,
+        }
+    }
+
+    pub const fn quote_style(mut self, style: QuoteStyle) -> Self {
+        self.quote_style = style;
+        self
+    }
+
+    /// `>` is not required in text content; `false` leaves it literal.
+    pub const fn escape_gt(mut self, escape: bool) -> Self {
+        self.escape_gt = escape;
+        self
+    }
+
+    /// `/` becomes `&#47;` under [`QuoteStyle::Decimal`] and `&#x2F;`
+    /// otherwise.
+    pub const fn escape_slash(mut self, escape: bool) -> Self {
+        self.escape_slash = escape;
+        self
+    }
+
+    /// How C0 controls other than tab, LF and CR, and DEL, are handled.
+    /// Encoded references are hex under [`QuoteStyle::Hex`] and decimal
+    /// otherwise.
+    pub const fn control_chars(mut self, policy: ControlPolicy) -> Self {
+        self.control_chars = policy;
+        self
+    }
+
+    fn entity(&self, ch: char) -> Option<&'static str> {
+        if self.control_chars == ControlPolicy::Encode && is_disallowed_control(ch) {
+            return Some(match (ch, self.quote_style) {
+                ('\x7f', QuoteStyle::Hex) => "&#x7F;",
+                ('\x7f', _) => "&#127;",
+                (_, QuoteStyle::Hex) => CONTROL_HEX[ch as usize],
+                _ => CONTROL_DECIMAL[ch as usize],
+            });
+        }
+
+        match ch {
+            '>' if !self.escape_gt => None,
+            '/' if self.escape_slash => Some(match self.quote_style {
+                QuoteStyle::Decimal => "&#47;",
+                QuoteStyle::Hex | QuoteStyle::Named => "&#x2F;",
+            }),
+            _ if ch.is_ascii() => rysafe_core::replacement(ch as u8, self.quote_style),
+            _ => None,
+        }
+    }
+
+    // Every character the options can replace is ASCII, so byte scans are exact.
+    fn byte_entity(&self, byte: u8) -> Option<&'static str> {
+        if byte.is_ascii() {
+            self.entity(byte as char)
+        } else {
+            None
+        }
+    }
+
+    fn needs_escaping(&self, input: &str) -> bool {
+        input.bytes().any(|byte| self.byte_entity(byte).is_some())
+    }
+
+    // Saturates rather than wrapping, so an impossible size reaches the
+    // allocator as a capacity overflow instead of a too-small buffer.
+    fn escaped_len(&self, input: &str) -> usize {
+        input.bytes().fold(0usize, |len, byte| {
+            len.saturating_add(self.byte_entity(byte).map_or(1, str::len))
+        })
+    }
+}
+
+const DEFAULT_OPTIONS: EscapeOptions = EscapeOptions::new();
+
+const OWASP_OPTIONS: EscapeOptions = EscapeOptions::new()
+    .quote_style(QuoteStyle::Hex)
+    .escape_slash(true);
+
+fn html_entity(ch: char) -> Option<&'static str> {
+    if ch.is_ascii() {
+        rysafe_core::replacement(ch as u8, QuoteStyle::Decimal)
+    } else {
+        None
+    }
+}
+
+/// Escapes `&`, `<`, `>`, `"` and `'` for use in HTML text and quoted
+/// attributes, borrowing the input when nothing needs replacing. This is
+/// [`escape_html_with`] under the default [`EscapeOptions`].
+pub fn escape_html(input: &str) -> Cow<'_, str> {
+    escape_html_with(input, &DEFAULT_OPTIONS)
+}
+
+/// Splits `input` at its first special character: the safe prefix, borrowed,
+/// and the escaped remainder. Concatenated they are `escape_html(input)`; for
+/// safe input the remainder is empty.
+pub fn escape_html_split(input: &str) -> (&str, Cow<'_, str>) {
+    let bytes = input.as_bytes();
+    let end = memchr::memchr3(b'&', b'<', b'>', bytes).unwrap_or(bytes.len());
+    // Quotes only matter if they come before the first `&`, `<` or `>`.
+    let first = memchr::memchr2(b'"', b'\'', &bytes[..end]).unwrap_or(end);
+    if first == bytes.len() {
+        return (input, Cow::Borrowed(""));
+    }
+    let (prefix, rest) = input.split_at(first);
+    (prefix, escape_html(rest))
+}
+
+/// [`escape_html`] that also returns how many characters were replaced.
+pub fn escape_html_counted(input: &str) -> (Cow<'_, str>, usize) {
+    let escaped = escape_html(input);
+    let count = match escaped {
+        Cow::Borrowed(_) => 0,
+        Cow::Owned(_) => input
+            .bytes()
+            .filter(|&byte| html_entity_byte(byte).is_some())
+            .count(),
+    };
+    (escaped, count)
+}
+
+/// [`escape_html`] for input that is already a `Cow`: safe input is handed
+/// back as is, keeping an `Owned` buffer instead of copying it.
+pub fn escape_html_cow(input: Cow<'_, str>) -> Cow<'_, str> {
+    match escape_html(&input) {
+        Cow::Borrowed(_) => input,
+        Cow::Owned(escaped) => Cow::Owned(escaped),
+    }
+}
+
+/// MarkupSafe's `escape_silent`: [`escape_html`], with `None` giving an empty
+/// string.
+pub fn escape_silent(input: Option<&str>) -> Cow<'_, str> {
+    input.map_or(Cow::Borrowed(""), escape_html)
+}
+
+/// [`escape_html`] with the replacement set and entity forms chosen by `opts`.
+///
+/// This cannot fail, so `ControlPolicy::Error` passes control characters
+/// through here; use [`try_escape_html_with`] to enforce it.
+pub fn escape_html_with<'a>(input: &'a str, opts: &EscapeOptions) -> Cow<'a, str> {
+    // The defaults are exactly the core escape, with its memchr fast path.
+    if *opts == DEFAULT_OPTIONS {
+        return rysafe_core::escape(input);
+    }
+    if !opts.needs_escaping(input) {
+        return Cow::Borrowed(input);
+    }
+    let mut escaped = String::with_capacity(opts.escaped_len(input));
+    // Writing into a String cannot fail.
+    let _ = escape_with_to(input, &mut escaped, |ch| opts.entity(ch));
+    Cow::Owned(escaped)
+}
+
+/// [`escape_html`] for untrusted input of unknown size: anything longer than
+/// [`MAX_INPUT_SIZE`] bytes is refused before any work is done.
+pub fn try_escape_html(input: &str) -> EscapeResult<Cow<'_, str>> {
+    try_escape_html_with_limit(input, MAX_INPUT_SIZE)
+}
+
+/// [`try_escape_html`] with a caller-chosen ceiling of `max_bytes`.
+pub fn try_escape_html_with_limit(input: &str, max_bytes: usize) -> EscapeResult<Cow<'_, str>> {
+    if input.len() > max_bytes {
+        return Err(EscapeError::InputTooLarge {
+            len: input.len(),
+            max: max_bytes,
+        });
+    }
+    Ok(escape_html(input))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NulPolicy {
+    #[default]
+    Reject,
+    Strip,
+}
+
+/// [`escape_html`] for output bound for C strings: embedded NUL bytes are
+/// rejected as [`validate_utf8`] does, or dropped with `NulPolicy::Strip`.
+pub fn try_escape_html_nul(input: &str, policy: NulPolicy) -> EscapeResult<Cow<'_, str>> {
+    if !input.contains('\0') {
+        return Ok(escape_html(input));
+    }
+    match policy {
+        NulPolicy::Reject => validate_utf8(input.as_bytes()).map(escape_html),
+        NulPolicy::Strip => {
+            let stripped: String = input.chars().filter(|&ch| ch != '\0').collect();
+            Ok(Cow::Owned(escape_html(&stripped).into_owned()))
+        }
+    }
+}
+
+/// [`escape_html_with`], but under `ControlPolicy::Error` a disallowed control
+/// character is reported with its byte offset instead of being copied.
+pub fn try_escape_html_with<'a>(
+    input: &'a str,
+    opts: &EscapeOptions,
+) -> EscapeResult<Cow<'a, str>> {
+    if opts.control_chars == ControlPolicy::Error {
+        if let Some((position, ch)) = input
+            .char_indices()
+            .find(|&(_, ch)| is_disallowed_control(ch))
+        {
+            return Err(EscapeError::ControlCharacter { position, ch });
+        }
+    }
+    Ok(escape_html_with(input, opts))
+}
+
+/// Escapes the five HTML specials plus `/` in the forms the OWASP XSS cheat
+/// sheet recommends (`&#x27;`, `&#x2F;`). [`escape_html`] is unaffected.
+pub fn escape_html_owasp(input: &str) -> Cow<'_, str> {
+    escape_html_with(input, &OWASP_OPTIONS)
+}
+
+/// Returns true if `input` contains any character [`escape_html`] replaces.
+pub fn needs_escaping(input: &str) -> bool {
+    rysafe_core::needs_escaping(input)
+}
+
+/// Exact byte length of `escape_html(input)`, computed in one scan without
+/// allocating.
+pub fn escaped_len(input: &str) -> usize {
+    rysafe_core::escaped_len(input, QuoteStyle::Decimal)
+}
+
+/// Writes the escaped form of `input` straight into `out`, as [`escape_html`]
+/// would produce it. Safe input is forwarded with a single `write_str`.
+pub fn escape_html_to<W: fmt::Write>(input: &str, out: &mut W) -> fmt::Result {
+    rysafe_core::escape_to(input, out)
+}
+
+/// Writes `escape_html(input)` into the start of `out` without allocating and
+/// returns `Ok(written)`. If `out` is too small nothing is written and
+/// `Err(required)` gives the length to retry with.
+pub fn escape_html_into_slice(input: &str, out: &mut [u8]) -> Result<usize, usize> {
+    let required = escaped_len(input);
+    if required > out.len() {
+        return Err(required);
+    }
+    let mut written = 0;
+    let _ = escape_bytes_runs(input.as_bytes(), |run| {
+        out[written..written + run.len()].copy_from_slice(run);
+        written += run.len();
+        Ok::<_, Infallible>(())
+    });
+    Ok(written)
+}
+
+/// [`escape_html`] one line at a time. Each item keeps its own terminator
+/// (`\n` or `\r\n`, none on an unterminated last line), so concatenating the
+/// items reproduces `escape_html(input)` exactly.
+pub fn escape_html_lines(input: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    input.split_inclusive('\n').map(escape_html)
+}
+
+/// [`escape_html`] for output that must be pure ASCII: additionally, every
+/// code point from U+0080 up becomes one hex reference (`é` is `&#xE9;`,
+/// `🌍` is `&#x1F30D;`, never a surrogate pair). Unescaping gives the input
+/// back, except for the C1 controls U+0080 to U+009F: HTML5 reads most of
+/// `&#x80;`..`&#x9F;` as Windows-1252 (`&#x80;` is `€`), so no reference
+/// round-trips and they are replaced with `&#xFFFD;` instead.
+pub fn escape_html_ascii(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len() + 16);
+    let mut last_end = 0;
+
+    for (i, ch) in input.char_indices() {
+        if ch.is_ascii() && html_entity(ch).is_none() {
+            continue;
+        }
+        escaped.push_str(&input[last_end..i]);
+        match html_entity(ch) {
+            Some(entity) => escaped.push_str(entity),
+            None if ('\u{80}'..='\u{9f}').contains(&ch) => escaped.push_str("&#xFFFD;"),
+            None => {
+                let _ = write!(escaped, "&#x{:X};", ch as u32);
+            }
+        }
+        last_end = i + ch.len_utf8();
+    }
+
+    escaped.push_str(&input[last_end..]);
+    escaped
+}
+
+// Inputs shorter than this are escaped serially; splitting them costs more
+// than the scan.
+#[cfg(feature = "rayon")]
+const PARALLEL_MIN_LEN: usize = 1 << 20;
+
+/// [`escape_html`] spread across the rayon thread pool (with the `rayon`
+/// feature). The input is cut into one chunk per thread, each cut moved
+/// forward to a char boundary; the specials are all ASCII, so escaping the
+/// chunks separately gives the same result as escaping the whole.
+///
+/// Inputs under 1 MiB, or a pool of one thread, take the serial path.
+#[cfg(feature = "rayon")]
+pub fn escape_html_parallel(input: &str) -> String {
+    use rayon::prelude::*;
+
+    let threads = rayon::current_num_threads();
+    if input.len() < PARALLEL_MIN_LEN || threads == 1 {
+        return escape_html(input).into_owned();
+    }
+    let chunk_len = input.len().div_ceil(threads);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < input.len() {
+        let mut end = (start + chunk_len).min(input.len());
+        while !input.is_char_boundary(end) {
+            end += 1;
+        }
+        chunks.push(&input[start..end]);
+        start = end;
+    }
+
+    let escaped: Vec<Cow<'_, str>> = chunks.par_iter().map(|chunk| escape_html(chunk)).collect();
+    let mut out = String::with_capacity(escaped.iter().map(|part| part.len()).sum());
+    for part in &escaped {
+        out.push_str(part);
+    }
+    out
+}
+
+// `html_entity_byte` for const contexts; an empty slice means "copy as is".
+const fn const_entity(byte: u8) -> &'static [u8] {
+    match rysafe_core::replacement(byte, QuoteStyle::Decimal) {
+        Some(entity) => entity.as_bytes(),
+        None => b"",
+    }
+}
+
+/// Length of `escape_html(input)`, computable at compile time.
+pub const fn escaped_html_len(input: &str) -> usize {
+    rysafe_core::escaped_len(input, QuoteStyle::Decimal)
+}
+
+/// The bytes of `escape_html(input)` as an array, for const contexts; `N`
+/// must be [`escaped_html_len`]. [`escape_html_const!`](crate::escape_html_const)
+/// wraps this into a `&'static str`.
+///
+/// # Panics
+///
+/// If `N` is not the escaped length (at compile time when evaluated in a
+/// `const`).
+pub const fn escape_html_array<const N: usize>(input: &str) -> [u8; N] {
+    assert!(
+        N == escaped_html_len(input),
+        "N must equal escaped_html_len(input)"
+    );
+    let bytes = input.as_bytes();
+    let mut out = [0; N];
+    let (mut i, mut o) = (0, 0);
+    while i < bytes.len() {
+        let entity = const_entity(bytes[i]);
+        if entity.is_empty() {
+            out[o] = bytes[i];
+            o += 1;
+        } else {
+            let mut j = 0;
+            while j < entity.len() {
+                out[o] = entity[j];
+                o += 1;
+                j += 1;
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Escapes a string constant at compile time into a `&'static str`:
+///
+/// ```
+/// const TITLE: &str = rysafe::escape_html_const!("Tom & 'Jerry' <3");
+/// assert_eq!(TITLE, "Tom &amp; &#39;Jerry&#39; &lt;3");
+/// ```
+#[macro_export]
+macro_rules! escape_html_const {
+    ($input:expr) => {{
+        const INPUT: &str = $input;
+        const BYTES: [u8; $crate::escaped_html_len(INPUT)] = $crate::escape_html_array(INPUT);
+        // Only ASCII bytes were replaced, so this cannot fail.
+        const ESCAPED: &str = match ::core::str::from_utf8(&BYTES) {
+            Ok(escaped) => escaped,
+            Err(_) => panic!("escaped output is not UTF-8"),
+        };
+        ESCAPED
+    }};
+}
+
+/// [`escape_html`] for displaying arbitrary user text, in the same pass
+/// dropping control characters (C0, DEL and C1) other than tab, newline and
+/// carriage return.
+pub fn sanitize_text(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut last_end = 0;
+
+    for (i, ch) in input.char_indices() {
+        let replacement = match ch {
+            '\t' | '\n' | '\r' => continue,
+            c if c.is_control() => "",
+            c => match html_entity(c) {
+                Some(entity) => entity,
+                None => continue,
+            },
+        };
+        out.push_str(&input[last_end..i]);
+        out.push_str(replacement);
+        last_end = i + ch.len_utf8();
+    }
+
+    out.push_str(&input[last_end..]);
+    out
+}
+
+/// [`escape_html`] over a whole slice: safe elements are borrowed, the rest
+/// each get their own exactly sized `String`.
+pub fn escape_many<'a>(inputs: &[&'a str]) -> Vec<Cow<'a, str>> {
+    inputs.iter().map(|&input| escape_html(input)).collect()
+}
+
+/// Escapes only the characters listed in `chars`, using the [`escape_html`]
+/// entity for each. Characters outside the five HTML specials are ignored, so
+/// passing all five reproduces [`escape_html`].
+pub fn escape_chars<'a>(input: &'a str, chars: &[char]) -> Cow<'a, str> {
+    escape_with(input, |ch| {
+        if chars.contains(&ch) {
+            html_entity(ch)
+        } else {
+            None
+        }
+    })
+}
+
+/// Like [`escape_html`], but emits the XML-idiomatic `&apos;` for `'`.
+pub fn escape_xml(input: &str) -> Cow<'_, str> {
+    escape_with(input, |ch| match ch {
+        '\'' => Some("&apos;"),
+        _ => html_entity(ch),
+    })
+}
+
+/// Appends the escaped form of `input` to `out`, leaving existing contents
+/// alone so the caller can clear and reuse one buffer across many calls.
+pub fn escape_html_into_string(input: &str, out: &mut String) {
+    // Writing into a String cannot fail.
+    let _ = escape_html_to(input, out);
+}
+
+fn html_entity_byte(byte: u8) -> Option<&'static [u8]> {
+    rysafe_core::replacement(byte, QuoteStyle::Decimal).map(str::as_bytes)
+}
+
+/// Byte-oriented [`escape_html`]. All other bytes, including invalid UTF-8,
+/// are copied through verbatim.
+///
+/// Only the ASCII bytes of the five specials are matched, so the input must
+/// be in an ASCII-compatible encoding (UTF-8, Latin-1, ...). EBCDIC and
+/// UTF-16 text comes out wrong without any error; use
+/// [`escape_html_bytes_checked`] when the encoding is not known.
+pub fn escape_html_bytes(input: &[u8]) -> Vec<u8> {
+    escape_html_bytes_cow(input).into_owned()
+}
+
+/// [`escape_html_bytes`] for input that must be UTF-8: anything else is
+/// [`EscapeError::InvalidUtf8`] instead of being escaped as if it were ASCII.
+/// NUL bytes are valid UTF-8 and are accepted.
+pub fn escape_html_bytes_checked(input: &[u8]) -> EscapeResult<Vec<u8>> {
+    let text = core::str::from_utf8(input).map_err(|_| EscapeError::InvalidUtf8)?;
+    Ok(escape_html(text).into_owned().into_bytes())
+}
+
+/// Like [`escape_html_bytes`], but borrows `input` when no byte needs escaping.
+pub fn escape_html_bytes_cow(input: &[u8]) -> Cow<'_, [u8]> {
+    if !input.iter().any(|&byte| html_entity_byte(byte).is_some()) {
+        return Cow::Borrowed(input);
+    }
+    let mut out = Vec::with_capacity(input.len() + 16);
+    let _ = escape_bytes_runs(input, |run| {
+        out.extend_from_slice(run);
+        Ok::<_, Infallible>(())
+    });
+    Cow::Owned(out)
+}
+
+/// Streams the escaped bytes of `input` into `out`, writing each run of safe
+/// bytes as one slice. Returns the number of input bytes processed.
+#[cfg(feature = "std")]
+pub fn escape_html_bytes_to<W: io::Write>(input: &[u8], out: &mut W) -> io::Result<usize> {
+    escape_bytes_runs(input, |run| out.write_all(run))?;
+    Ok(input.len())
+}
+
+/// [`escape_html_bytes_cow`] for file names and other OS strings: the
+/// specials are escaped and anything that is not valid UTF-8 is kept as is,
+/// instead of being lost to a lossy conversion first.
+#[cfg(feature = "std")]
+pub fn escape_os_str(input: &OsStr) -> Cow<'_, OsStr> {
+    match escape_html_bytes_cow(input.as_encoded_bytes()) {
+        Cow::Borrowed(_) => Cow::Borrowed(input),
+        // SAFETY: only ASCII bytes were replaced, and only with ASCII, so the
+        // result is the original encoding split and rejoined at valid UTF-8
+        // boundaries.
+        Cow::Owned(bytes) => Cow::Owned(unsafe { OsString::from_encoded_bytes_unchecked(bytes) }),
+    }
+}
+
+/// Appends `escape_html(input)` to `out` (with the `bytes` feature), so a
+/// response body can be built without an intermediate `String`.
+#[cfg(feature = "bytes")]
+pub fn escape_html_to_bytesmut(input: &str, out: &mut bytes::BytesMut) {
+    if !needs_escaping(input) {
+        out.extend_from_slice(input.as_bytes());
+        return;
+    }
+    out.reserve(escaped_len(input));
+    let _ = escape_bytes_runs(input.as_bytes(), |run| {
+        out.extend_from_slice(run);
+        Ok::<_, Infallible>(())
+    });
+}
+
+// Hands `emit` alternating runs of safe bytes and entities, in order.
+fn escape_bytes_runs<E>(
+    input: &[u8],
+    mut emit: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut last_end = 0;
+
+    for (i, &byte) in input.iter().enumerate() {
+        let Some(entity) = html_entity_byte(byte) else {
+            continue;
+        };
+        if last_end < i {
+            emit(&input[last_end..i])?;
+        }
+        emit(entity)?;
+        last_end = i + 1;
+    }
+
+    if last_end < input.len() {
+        emit(&input[last_end..])?;
+    }
+    Ok(())
+}
+
+/// Escapes a value for an HTML attribute (`value="..."`).
+///
+/// On top of the [`escape_html`] set, tab, newline, carriage return and form
+/// feed become numeric references so the value cannot spill out of a sloppily
+/// quoted attribute:
+///
+/// ```
+/// let value = rysafe::escape_attribute("line one\n\" onload=\"x");
+/// assert_eq!(value, "line one&#10;&#34; onload=&#34;x");
+/// ```
+pub fn escape_attribute(input: &str) -> Cow<'_, str> {
+    escape_with(input, |ch| match ch {
+        '\t' => Some("&#9;"),
+        '\n' => Some("&#10;"),
+        '\x0C' => Some("&#12;"),
+        '\r' => Some("&#13;"),
+        _ => html_entity(ch),
+    })
+}
+
+/// Escapes an attribute value that will be wrapped in `delimiter` quotes.
+///
+/// Only `&`, `<`, `>` and the delimiter itself are escaped, so
+/// `escape_attr_value("it's", '"')` stays `it's`. The output is only safe if
+/// the caller really quotes the attribute with `delimiter`; any delimiter
+/// other than `'` or `"` escapes both quotes.
+pub fn escape_attr_value(input: &str, delimiter: char) -> Cow<'_, str> {
+    let (double, single) = match delimiter {
+        '"' => (true, false),
+        '\'' => (false, true),
+        _ => (true, true),
+    };
+    escape_with(input, |ch| match ch {
+        '"' if !double => None,
+        '\'' if !single => None,
+        _ => html_entity(ch),
+    })
+}
+
+/// The minimum for a `"`-quoted attribute value: only `&` and `"` are
+/// escaped; `<`, `>` and `'` are left as they are.
+///
+/// Only safe inside a properly double-quoted attribute. Text content, unquoted
+/// or single-quoted attributes need [`escape_html`].
+pub fn escape_attr_double_quoted(input: &str) -> Cow<'_, str> {
+    escape_with(input, |ch| match ch {
+        '&' | '"' => html_entity(ch),
+        _ => None,
+    })
+}
+
+/// [`escape_attr_double_quoted`] for a `'`-quoted value: only `&` and `'`
+/// are escaped. Only safe inside a properly single-quoted attribute.
+pub fn escape_attr_single_quoted(input: &str) -> Cow<'_, str> {
+    escape_with(input, |ch| match ch {
+        '&' | '\'' => html_entity(ch),
+        _ => None,
+    })
+}
+
+/// Escapes text for a quoted JavaScript string inside an inline `<script>`.
+///
+/// This is not HTML escaping: quotes, backslashes and line terminators are
+/// backslash-escaped, and `<`, `>`, `&` and `/` are encoded so a literal
+/// `</script>` in the value cannot close the block.
+pub fn escape_js_string(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len() + 16);
+    let mut last_end = 0;
+
+    for (i, ch) in input.char_indices() {
+        let replacement = match ch {
+            '\'' => Some("\\'"),
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            '<' => Some("\\x3C"),
+            '>' => Some("\\x3E"),
+            '&' => Some("\\x26"),
+            '/' => Some("\\/"),
+            '\u{2028}' => Some("\\u2028"),
+            '\u{2029}' => Some("\\u2029"),
+            c if c.is_ascii_control() => None,
+            _ => continue,
+        };
+        escaped.push_str(&input[last_end..i]);
+        match replacement {
+            Some(replacement) => escaped.push_str(replacement),
+            None => {
+                let _ = write!(escaped, "\\x{:02X}", ch as u32);
+            }
+        }
+        last_end = i + ch.len_utf8();
+    }
+
+    escaped.push_str(&input[last_end..]);
+    escaped
+}
+
+/// Percent-encodes a URL query parameter or path segment. Every UTF-8 byte
+/// outside the RFC 3986 unreserved set (`A-Za-z0-9-._~`) becomes `%XX`.
+pub fn escape_url_component(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len() + 16);
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                escaped.push(byte as char)
+            }
+            _ => {
+                let _ = write!(escaped, "%{:02X}", byte);
+            }
+        }
+    }
+    escaped
+}
+
+/// Escapes text for a CSS identifier or string in a `style` attribute or
+/// `<style>` block. Everything outside `[a-zA-Z0-9]`, and a leading digit,
+/// becomes a `\XXXXXX` hex escape; a space terminates the escape when the next
+/// character is a hex digit. U+0000 is replaced with U+FFFD.
+pub fn escape_css(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len() + 16);
+    let mut chars = input.chars().peekable();
+    let mut first = true;
+
+    while let Some(ch) = chars.next() {
+        let leading_digit = first && ch.is_ascii_digit();
+        first = false;
+        if ch.is_ascii_alphanumeric() && !leading_digit {
+            escaped.push(ch);
+            continue;
+        }
+        let code = if ch == '\0' { 0xFFFD } else { ch as u32 };
+        let _ = write!(escaped, "\\{:X}", code);
+        if chars.peek().is_some_and(|next| next.is_ascii_hexdigit()) {
+            escaped.push(' ');
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntityKind {
+    Named,
+    Numeric,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnescapeStats {
+    pub named: usize,
+    pub numeric: usize,
+    pub malformed: usize,
+    pub by_entity: BTreeMap<String, usize>,
+    pub malformed_by_sequence: BTreeMap<String, usize>,
+}
+
+// What HTML5 substitutes for `&#128;`..`&#159;`: the Windows-1252 character
+// at that position, or the C1 control itself where Windows-1252 has a hole.
+const WINDOWS_1252_C1: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+// Enough for every code point (`&#1114111;`, `&#x10FFFF;`) with some zero
+// padding; longer runs are left as text rather than parsed.
+const MAX_NUMERIC_DIGITS: usize = 8;
+
+fn numeric_entity(digits: &str) -> Option<char> {
+    let (digits, radix) = match digits.strip_prefix(['x', 'X']) {
+        Some(hex) => (hex, 16),
+        None => (digits, 10),
+    };
+    if digits.len() > MAX_NUMERIC_DIGITS {
+        return None;
+    }
+    let code = u32::from_str_radix(digits, radix).ok()?;
+    Some(match code {
+        // HTML5 never decodes a reference to U+0000.
+        0 => char::REPLACEMENT_CHARACTER,
+        0x80..=0x9f => WINDOWS_1252_C1[(code - 0x80) as usize],
+        // Surrogates and code points past U+10FFFF.
+        _ => char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Decoded<'t> {
+    Char(char),
+    // Some named references expand to two code points, e.g. `&NotEqualTilde;`.
+    Str(&'t str),
+}
+
+impl Decoded<'_> {
+    fn push_to(self, out: &mut String) {
+        match self {
+            Decoded::Char(ch) => out.push(ch),
+            Decoded::Str(s) => out.push_str(s),
+        }
+    }
+
+    fn extend_bytes(self, out: &mut Vec<u8>) {
+        match self {
+            Decoded::Char(ch) => out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+            Decoded::Str(s) => out.extend_from_slice(s.as_bytes()),
+        }
+    }
+}
+
+fn decode_entity(name: &str) -> Option<(Decoded<'static>, EntityKind)> {
+    match name.strip_prefix('#') {
+        Some(digits) => numeric_entity(digits).map(|ch| (Decoded::Char(ch), EntityKind::Numeric)),
+        None => entities::lookup(name).map(|s| (Decoded::Str(s), EntityKind::Named)),
+    }
+}
+
+// Longer than any named reference (`CounterClockwiseContourIntegral` is 31).
+const MAX_ENTITY_NAME_LEN: usize = 32;
+
+// Splits the bytes after an `&` into the candidate entity name and whether it
+// is terminated by `;`. The scan never looks further than MAX_ENTITY_NAME_LEN
+// bytes, so a stray `&` before a long run of text stays cheap. Names are
+// ASCII, so the split always lands on a UTF-8 boundary.
+fn entity_candidate(rest: &[u8]) -> (&str, bool) {
+    let window = &rest[..rest.len().min(MAX_ENTITY_NAME_LEN + 1)];
+    let (end, terminated) = match window
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || b == b'#'))
+    {
+        Some(end) => (end, window[end] == b';'),
+        None => (window.len().min(MAX_ENTITY_NAME_LEN), false),
+    };
+    (ascii_str(&rest[..end]), terminated)
+}
+
+fn ascii_str(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or_default()
+}
+
+struct EntityRef<'a> {
+    start: usize,
+    name: &'a str,
+    terminated: bool,
+    text: &'a str,
+    decoded: Option<(Decoded<'a>, EntityKind)>,
+}
+
+/// The named references an unescaper knows. Numeric references decode under
+/// either set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntitySet {
+    /// Only XML's predefined `&lt; &gt; &amp; &quot; &apos;`, each needing
+    /// its `;`. `&copy;` and the like stay literal.
+    Xml,
+    /// Every HTML5 named reference, with the legacy no-`;` forms.
+    #[default]
+    Html5,
+}
+
+fn xml_entity(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "lt" => "<",
+        "gt" => ">",
+        "amp" => "&",
+        "quot" => "\"",
+        "apos" => "'",
+        _ => return None,
+    })
+}
+
+/// Named references for [`unescape_html_with_table`]: the standard HTML5 set,
+/// extended with [`insert`](Self::insert) or trimmed with
+/// [`remove`](Self::remove).
+///
+/// Names are given without `&` and `;`. Only ASCII alphanumeric names of at
+/// most 32 bytes can ever match, and added names always need their `;`.
+#[derive(Debug, Clone, Default)]
+pub struct EntityTable {
+    // `None` hides a standard name.
+    overrides: BTreeMap<String, Option<String>>,
+}
+
+impl EntityTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes `&name;` as `replacement`, replacing any standard meaning.
+    pub fn insert(mut self, name: &str, replacement: &str) -> Self {
+        self.overrides
+            .insert(name.to_string(), Some(replacement.to_string()));
+        self
+    }
+
+    /// Leaves `&name;`, and its legacy no-`;` form if any, as literal text.
+    pub fn remove(mut self, name: &str) -> Self {
+        self.overrides.insert(name.to_string(), None);
+        self
+    }
+
+    fn lookup(&self, name: &str) -> Option<&str> {
+        match self.overrides.get(name) {
+            Some(custom) => custom.as_deref(),
+            None => entities::lookup(name),
+        }
+    }
+}
+
+// Which references a decoder accepts beyond the terminated ones and the
+// legacy names.
+#[derive(Debug, Clone, Copy, Default)]
+struct Decoding<'t> {
+    // `&#60` and `&#x3C` without the `;`.
+    bare_numeric: bool,
+    // Leave every named reference, `&amp;` included, as literal text.
+    numeric_only: bool,
+    // Skip the legacy names, so `&lt` without `;` stays literal.
+    require_semicolon: bool,
+    entities: EntitySet,
+    // Replaces the standard HTML5 names.
+    table: Option<&'t EntityTable>,
+}
+
+// The leading `#digits` or `#xhex` of an unterminated candidate, decoded.
+fn numeric_prefix(name: &str) -> Option<(usize, char)> {
+    let digits = name.strip_prefix('#')?;
+    let (skip, is_digit): (usize, fn(&u8) -> bool) = match digits.strip_prefix(['x', 'X']) {
+        Some(_) => (1, u8::is_ascii_hexdigit),
+        None => (0, u8::is_ascii_digit),
+    };
+    let count = digits.as_bytes()[skip..]
+        .iter()
+        .take_while(|b| is_digit(b))
+        .count();
+    if count == 0 {
+        return None;
+    }
+    let len = 1 + skip + count;
+    numeric_entity(&name[1..len]).map(|ch| (len, ch))
+}
+
+// What the candidate after an `&` decodes to under `decoding`, and how many of
+// its bytes (including any `;`) the reference spans.
+fn decode_candidate<'t>(
+    name: &str,
+    terminated: bool,
+    decoding: Decoding<'t>,
+) -> Option<(usize, (Decoded<'t>, EntityKind))> {
+    let named = !decoding.numeric_only;
+    let complete = terminated
+        .then(|| {
+            if name.starts_with('#') {
+                return decode_entity(name);
+            }
+            let value = match (decoding.entities, decoding.table) {
+                (EntitySet::Xml, _) => xml_entity(name),
+                (EntitySet::Html5, Some(table)) => table.lookup(name),
+                (EntitySet::Html5, None) => entities::lookup(name),
+            };
+            value.map(|s| (Decoded::Str(s), EntityKind::Named))
+        })
+        .flatten()
+        .filter(|&(_, kind)| named || kind == EntityKind::Numeric)
+        .map(|decoded| (name.len() + 1, decoded));
+    let legacy = || {
+        if !named || decoding.require_semicolon || decoding.entities == EntitySet::Xml {
+            return None;
+        }
+        let (len, value) = entities::legacy_prefix(name)?;
+        if let Some(table) = decoding.table {
+            if table.overrides.contains_key(&name[..len]) {
+                return None;
+            }
+        }
+        Some((len, (Decoded::Str(value), EntityKind::Named)))
+    };
+    let bare_numeric = || {
+        if !decoding.bare_numeric {
+            return None;
+        }
+        let (len, ch) = numeric_prefix(name)?;
+        Some((len, (Decoded::Char(ch), EntityKind::Numeric)))
+    };
+    complete.or_else(legacy).or_else(bare_numeric)
+}
+
+// Yields every `&` in `input` together with the reference it starts, if any.
+// Works on bytes so the str and byte unescapers share it.
+fn entity_refs(input: &[u8]) -> impl Iterator<Item = EntityRef<'_>> {
+    entity_refs_with(input, Decoding::default())
+}
+
+fn entity_refs_with<'a>(
+    input: &'a [u8],
+    decoding: Decoding<'a>,
+) -> impl Iterator<Item = EntityRef<'a>> {
+    let mut pos = 0;
+    core::iter::from_fn(move || {
+        let start = pos + input[pos..].iter().position(|&b| b == b'&')?;
+        let (name, terminated) = entity_candidate(&input[start + 1..]);
+        let (len, decoded) = match decode_candidate(name, terminated, decoding) {
+            Some((len, decoded)) => (len, Some(decoded)),
+            None => (name.len() + terminated as usize, None),
+        };
+        let end = start + 1 + len;
+        pos = if decoded.is_some() { end } else { start + 1 };
+        Some(EntityRef {
+            start,
+            name,
+            terminated,
+            text: ascii_str(&input[start..end]),
+            decoded,
+        })
+    })
+}
+
+fn unescape_impl<'a>(
+    input: &'a str,
+    decoding: Decoding<'_>,
+    mut stats: Option<&mut UnescapeStats>,
+) -> Cow<'a, str> {
+    let mut unescaped: Option<String> = None;
+    let mut last_end = 0;
+
+    for entity in entity_refs_with(input.as_bytes(), decoding) {
+        match entity.decoded {
+            Some((decoded, kind)) => {
+                let s = unescaped.get_or_insert_with(|| String::with_capacity(input.len()));
+                s.push_str(&input[last_end..entity.start]);
+                decoded.push_to(s);
+                last_end = entity.start + entity.text.len();
+                if let Some(stats) = stats.as_deref_mut() {
+                    stats.record(kind, entity.text);
+                }
+            }
+            None => {
+                if let Some(stats) = stats.as_deref_mut() {
+                    if !entity.name.is_empty() {
+                        stats.record_malformed(entity.text);
+                    }
+                }
+            }
+        }
+    }
+
+    match unescaped {
+        Some(mut s) => {
+            s.push_str(&input[last_end..]);
+            Cow::Owned(s)
+        }
+        None => Cow::Borrowed(input),
+    }
+}
+
+impl UnescapeStats {
+    fn record(&mut self, kind: EntityKind, entity: &str) {
+        match kind {
+            EntityKind::Named => self.named += 1,
+            EntityKind::Numeric => self.numeric += 1,
+        }
+        *self.by_entity.entry(entity.to_string()).or_insert(0) += 1;
+    }
+
+    fn record_malformed(&mut self, sequence: &str) {
+        self.malformed += 1;
+        *self
+            .malformed_by_sequence
+            .entry(sequence.to_string())
+            .or_insert(0) += 1;
+    }
+}
+
+/// Decodes the HTML5 named references (`&lt;`, `&copy;`, `&NotEqualTilde;`,
+/// ...) and numeric references (`&#60;`, `&#x3C;`). As in HTML5 text, the
+/// legacy names (`&amp`, `&lt`, `&copy`, ...) also decode without `;`, taking
+/// the longest such prefix. Anything unrecognized is left as literal text.
+pub fn unescape_html(input: &str) -> Cow<'_, str> {
+    unescape_impl(input, Decoding::default(), None)
+}
+
+/// The most aggressive decoding HTML5 applies to text: everything
+/// [`unescape_html`] decodes, including multi-code-point expansions such as
+/// `&bne;`, plus numeric references missing their `;` (`&#60`, `&#x3C`).
+///
+/// Meant for extracting display text, e.g. from HTML email. It is not the
+/// inverse of [`escape_html`]'s security guarantees; don't feed its output back
+/// into markup.
+pub fn unescape_html_full(input: &str) -> Cow<'_, str> {
+    let decoding = Decoding {
+        bare_numeric: true,
+        ..Decoding::default()
+    };
+    unescape_impl(input, decoding, None)
+}
+
+/// Decodes only numeric references (`&#60;`, `&#x3C;`); named ones such as
+/// `&amp;` pass through untouched, so no new `&` ambiguity is introduced.
+pub fn unescape_numeric_only(input: &str) -> Cow<'_, str> {
+    let decoding = Decoding {
+        numeric_only: true,
+        ..Decoding::default()
+    };
+    unescape_impl(input, decoding, None)
+}
+
+/// Like [`unescape_html`], but only references terminated by `;` decode: a
+/// bare `&lt` or `&amp` is left as literal text instead of taking the HTML5
+/// legacy reading.
+pub fn unescape_html_require_semicolon(input: &str) -> Cow<'_, str> {
+    let decoding = Decoding {
+        require_semicolon: true,
+        ..Decoding::default()
+    };
+    unescape_impl(input, decoding, None)
+}
+
+/// [`unescape_html`] restricted to the named references in `entities`; with
+/// [`EntitySet::Html5`] the two are the same.
+pub fn unescape_entities(input: &str, entities: EntitySet) -> Cow<'_, str> {
+    let decoding = Decoding {
+        entities,
+        ..Decoding::default()
+    };
+    unescape_impl(input, decoding, None)
+}
+
+/// [`unescape_html`] with the named references taken from `table`; numeric
+/// references decode as usual.
+///
+/// ```
+/// use rysafe::{unescape_html_with_table, EntityTable};
+///
+/// let table = EntityTable::new().insert("company", "ACME").remove("copy");
+/// assert_eq!(
+///     unescape_html_with_table("&copy; &company; &amp;", &table),
+///     "&copy; ACME &"
+/// );
+/// ```
+pub fn unescape_html_with_table<'a>(input: &'a str, table: &EntityTable) -> Cow<'a, str> {
+    let decoding = Decoding {
+        table: Some(table),
+        ..Decoding::default()
+    };
+    unescape_impl(input, decoding, None)
+}
+
+fn remove_delimited(input: &str, open: &str, close: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find(open) {
+        let Some(end) = rest[start..].find(close) else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        rest = &rest[start + end + close.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Converts markup to plain text the way MarkupSafe's `striptags` does:
+/// comments and then tags are removed, runs of whitespace collapse to a
+/// single space, and entities are decoded.
+pub fn strip_tags(input: &str) -> String {
+    let text = remove_delimited(input, "<!--", "-->");
+    let text = remove_delimited(&text, "<", ">");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    unescape_html(&text).into_owned()
+}
+
+// True if `rest`, the bytes after an `&`, open a terminated reference that
+// decodes, such as `amp;` or `#39;`.
+fn is_complete_reference(rest: &[u8]) -> bool {
+    let (name, terminated) = entity_candidate(rest);
+    terminated && decode_entity(name).is_some()
+}
+
+/// Like [`escape_html`], but an `&` that starts a well-formed, known reference
+/// (`&amp;`, `&copy;`, `&#39;`) is left alone, so escaping is not repeated on
+/// text that is already partly escaped. Unknown or unterminated references
+/// such as `&notreal;` or `&amp` get their `&` escaped.
+pub fn escape_html_preserve_entities(input: &str) -> Cow<'_, str> {
+    let bytes = input.as_bytes();
+    let mut escaped: Option<String> = None;
+    let mut last_end = 0;
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        let Some(entity) = DEFAULT_OPTIONS.byte_entity(byte) else {
+            continue;
+        };
+        if byte == b'&' && is_complete_reference(&bytes[i + 1..]) {
+            continue;
+        }
+        let s = escaped.get_or_insert_with(|| String::with_capacity(input.len() + 16));
+        s.push_str(&input[last_end..i]);
+        s.push_str(entity);
+        last_end = i + 1;
+    }
+
+    match escaped {
+        Some(mut s) => {
+            s.push_str(&input[last_end..]);
+            Cow::Owned(s)
+        }
+        None => Cow::Borrowed(input),
+    }
+}
+
+// Start offsets of each `&amp;` that is followed by another complete
+// reference, as in `&amp;lt;`.
+fn double_escapes(input: &str) -> impl Iterator<Item = usize> + '_ {
+    input
+        .match_indices("&amp;")
+        .map(|(i, _)| i)
+        .filter(move |&i| is_complete_reference(&input.as_bytes()[i + 5..]))
+}
+
+/// Returns true if `input` looks escaped twice: it contains an `&amp;` whose
+/// remainder is itself a complete reference, such as `&amp;lt;` or
+/// `&amp;#39;`.
+pub fn is_double_escaped(input: &str) -> bool {
+    double_escapes(input).next().is_some()
+}
+
+/// Removes one layer of over-escaping by turning each `&amp;` found by
+/// [`is_double_escaped`] back into `&`: `&amp;lt;` becomes `&lt;`. Everything
+/// else, single-escaped references included, is left as is, so the result
+/// is still escaped markup.
+pub fn undo_double_escape(input: &str) -> Cow<'_, str> {
+    let mut undone: Option<String> = None;
+    let mut last_end = 0;
+
+    for i in double_escapes(input) {
+        let s = undone.get_or_insert_with(|| String::with_capacity(input.len()));
+        s.push_str(&input[last_end..=i]);
+        last_end = i + 5;
+    }
+
+    match undone {
+        Some(mut s) => {
+            s.push_str(&input[last_end..]);
+            Cow::Owned(s)
+        }
+        None => Cow::Borrowed(input),
+    }
+}
+
+/// Byte-oriented [`unescape_html`]: named and numeric references decode to
+/// their UTF-8 bytes and everything else, including invalid UTF-8, is copied
+/// through verbatim.
+pub fn unescape_html_bytes(input: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(input.len());
+    let mut last_end = 0;
+
+    for entity in entity_refs(input) {
+        if let Some((decoded, _)) = entity.decoded {
+            unescaped.extend_from_slice(&input[last_end..entity.start]);
+            decoded.extend_bytes(&mut unescaped);
+            last_end = entity.start + entity.text.len();
+        }
+    }
+
+    unescaped.extend_from_slice(&input[last_end..]);
+    unescaped
+}
+
+/// Incremental [`unescape_html`] for input that arrives in chunks.
+///
+/// A trailing `&` that could still grow into an entity is held back until the
+/// next [`push`](Unescaper::push) or [`finish`](Unescaper::finish), so
+/// references split across chunk boundaries decode the same as in one piece.
+#[derive(Debug, Clone, Default)]
+pub struct Unescaper {
+    pending: String,
+    entities: EntitySet,
+}
+
+impl Unescaper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An unescaper that decodes only the named references in `entities`.
+    pub fn with_entities(entities: EntitySet) -> Self {
+        Unescaper {
+            pending: String::new(),
+            entities,
+        }
+    }
+
+    /// Feeds the next chunk and returns the output that is now final.
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.pending.push_str(chunk);
+        let split = partial_entity_start(&self.pending).unwrap_or(self.pending.len());
+        let out = unescape_entities(&self.pending[..split], self.entities).into_owned();
+        self.pending.drain(..split);
+        out
+    }
+
+    /// Flushes whatever was held back, decoding it as end of input.
+    pub fn finish(self) -> String {
+        unescape_entities(&self.pending, self.entities).into_owned()
+    }
+}
+
+// Offset of a trailing `&` whose reference may be completed by more input.
+fn partial_entity_start(input: &str) -> Option<usize> {
+    let start = input.rfind('&')?;
+    let tail = &input.as_bytes()[start + 1..];
+    let open = tail.len() <= MAX_ENTITY_NAME_LEN
+        && tail.iter().all(|&b| b.is_ascii_alphanumeric() || b == b'#');
+    open.then_some(start)
+}
+
+/// Same as [`unescape_html`], but an unrecognized `&name;` reference is an
+/// error naming the entity and its byte offset instead of literal text.
+pub fn unescape_html_strict(input: &str) -> EscapeResult<Cow<'_, str>> {
+    // `&notanentity;` is unrecognized even though its `&not` prefix decodes.
+    let unknown = entity_refs(input.as_bytes()).find(|entity| {
+        entity.terminated && !entity.name.is_empty() && decode_entity(entity.name).is_none()
+    });
+    match unknown {
+        Some(entity) => Err(EscapeError::ProcessingError(format!(
+            "unrecognized entity &{}; at byte {}",
+            entity.name, entity.start
+        ))),
+        None => Ok(unescape_html(input)),
+    }
+}
+
+/// Same as [`unescape_html`], but also reports which entities were decoded
+/// and which `&`-sequences were left literal.
+pub fn unescape_html_stats(input: &str) -> (String, UnescapeStats) {
+    let mut stats = UnescapeStats::default();
+    let unescaped = unescape_impl(input, Decoding::default(), Some(&mut stats)).into_owned();
+    (unescaped, stats)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeFinding {
+    pub position: usize,
+    pub ch: char,
+    pub replacement: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityFinding {
+    pub position: usize,
+    pub entity: String,
+    pub decoded: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlAudit {
+    pub escapes: Vec<EscapeFinding>,
+    pub entities: Vec<EntityFinding>,
+}
+
+impl HtmlAudit {
+    pub fn is_clean(&self) -> bool {
+        self.escapes.is_empty() && self.entities.is_empty()
+    }
+}
+
+/// Reports what [`escape_html`] would replace and what [`unescape_html`]
+/// would decode, by byte offset, without producing any transformed output.
+pub fn audit_html(input: &str) -> HtmlAudit {
+    let escapes = input
+        .char_indices()
+        .filter_map(|(position, ch)| {
+            html_entity(ch).map(|replacement| EscapeFinding {
+                position,
+                ch,
+                replacement,
+            })
+        })
+        .collect();
+    let entities = entity_refs(input.as_bytes())
+        .filter_map(|entity| {
+            entity.decoded.map(|(decoded, _)| {
+                let mut text = String::new();
+                decoded.push_to(&mut text);
+                EntityFinding {
+                    position: entity.start,
+                    entity: entity.text.to_string(),
+                    decoded: text,
+                }
+            })
+        })
+        .collect();
+    HtmlAudit { escapes, entities }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use proptest::prelude::*;
+
+    // The per-character loops the run-copying escapers replaced.
+    fn escape_html_by_char(input: &str) -> String {
+        let mut escaped = String::new();
+        for ch in input.chars() {
+            match html_entity(ch) {
+                Some(entity) => escaped.push_str(entity),
+                None => escaped.push(ch),
+            }
+        }
+        escaped
+    }
+
+    fn escape_js_string_by_char(input: &str) -> String {
+        let mut escaped = String::new();
+        for ch in input.chars() {
+            match ch {
+                '\'' => escaped.push_str("\\'"),
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                '<' => escaped.push_str("\\x3C"),
+                '>' => escaped.push_str("\\x3E"),
+                '&' => escaped.push_str("\\x26"),
+                '/' => escaped.push_str("\\/"),
+                '\u{2028}' => escaped.push_str("\\u2028"),
+                '\u{2029}' => escaped.push_str("\\u2029"),
+                c if c.is_ascii_control() => {
+                    let _ = write!(escaped, "\\x{:02X}", c as u32);
+                }
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    proptest! {
+        #[test]
+        fn prop_escape_html_matches_by_char(input in any::<String>()) {
+            prop_assert_eq!(escape_html(&input), escape_html_by_char(&input));
+        }
+
+        #[test]
+        fn prop_escape_js_string_matches_by_char(input in any::<String>()) {
+            prop_assert_eq!(escape_js_string(&input), escape_js_string_by_char(&input));
+        }
+
+        #[test]
+        fn prop_needs_escaping_matches_options(input in "[&<>\"'a-z日 ]*") {
+            prop_assert_eq!(needs_escaping(&input), DEFAULT_OPTIONS.needs_escaping(&input));
+        }
+
+        #[test]
+        fn prop_unescape_inverts_escape(input in any::<String>()) {
+            let escaped = escape_html(&input);
+            prop_assert_eq!(unescape_html(&escaped), input.as_str());
+        }
+
+        #[test]
+        fn prop_unescape_inverts_escape_specials(input in "[&<>\"'#;a-z0-9x ]*") {
+            let escaped = escape_html(&input);
+            prop_assert_eq!(unescape_html(&escaped), input.as_str());
+            for style in [QuoteStyle::Decimal, QuoteStyle::Hex, QuoteStyle::Named] {
+                let opts = EscapeOptions::new().quote_style(style).escape_slash(true);
+                let escaped = escape_html_with(&input, &opts);
+                prop_assert_eq!(unescape_html(&escaped), input.as_str());
+            }
+        }
+
+        #[test]
+        fn prop_unescape_bytes_inverts_escape_bytes(input in any::<Vec<u8>>()) {
+            prop_assert_eq!(unescape_html_bytes(&escape_html_bytes(&input)), input);
+        }
+    }
+
+    #[test]
+    fn test_header_value_clean() {
+        assert_eq!(
+            escape_header_value("text/html; charset=utf-8").unwrap(),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(escape_header_value("a\tb").unwrap(), "a\tb");
+        assert!(matches!(
+            escape_header_value("plain").unwrap(),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_header_value_rejects_crlf() {
+        assert_eq!(
+            escape_header_value("value\r\nSet-Cookie: evil=1"),
+            Err(EscapeError::HeaderInjection {
+                position: 5,
+                ch: '\r'
+            })
+        );
+        assert_eq!(
+            escape_header_value("value\nX-Injected: 1"),
+            Err(EscapeError::HeaderInjection {
+                position: 5,
+                ch: '\n'
+            })
+        );
+        assert!(escape_header_value("nul\0byte").is_err());
+        assert!(escape_header_value("del\x7f").is_err());
+    }
+
+    #[test]
+    fn test_header_value_strip() {
+        assert_eq!(
+            escape_header_value_with("value\r\nSet-Cookie: evil=1", HeaderPolicy::Strip).unwrap(),
+            "valueSet-Cookie: evil=1"
+        );
+        assert_eq!(
+            escape_header_value_with("日本\r\n語", HeaderPolicy::Strip).unwrap(),
+            "日本語"
+        );
+    }
+
+    #[test]
+    fn test_unescape_basic() {
+        assert_eq!(unescape_html("plain"), "plain");
+        assert_eq!(
+            unescape_html("&lt;b&gt; &amp; &quot;q&quot; &apos;"),
+            "<b> & \"q\" '"
+        );
+        assert_eq!(unescape_html("&#60;&#x3C;&#X3c;"), "<<<");
+        assert_eq!(unescape_html("日本&lt;語"), "日本<語");
+    }
+
+    #[test]
+    fn test_unescape_malformed_passthrough() {
+        assert_eq!(unescape_html("a & b"), "a & b");
+        assert_eq!(unescape_html("&bogus;"), "&bogus;");
+        assert_eq!(unescape_html("&#xZZ;"), "&#xZZ;");
+        assert_eq!(unescape_html("&&lt;"), "&<");
+    }
+
+    #[test]
+    fn test_unescape_stats() {
+        let (text, stats) =
+            unescape_html_stats("&lt;p&gt; &amp;&amp; &#39;x&#x27; &bogus; &amp a & b &Bogus");
+        assert_eq!(text, "<p> && 'x' &bogus; & a & b &Bogus");
+        assert_eq!(stats.named, 5);
+        assert_eq!(stats.numeric, 2);
+        assert_eq!(stats.malformed, 2);
+        assert_eq!(stats.by_entity["&amp;"], 2);
+        assert_eq!(stats.by_entity["&amp"], 1);
+        assert_eq!(stats.by_entity["&lt;"], 1);
+        assert_eq!(stats.by_entity["&#39;"], 1);
+        assert_eq!(stats.by_entity["&#x27;"], 1);
+        assert_eq!(stats.malformed_by_sequence["&bogus;"], 1);
+        assert_eq!(stats.malformed_by_sequence["&Bogus"], 1);
+    }
+
+    #[test]
+    fn test_attr_value_double_quoted() {
+        assert_eq!(
+            escape_attr_value(r#"say "hi" & it's <ok>"#, '"'),
+            "say &#34;hi&#34; &amp; it's &lt;ok&gt;"
+        );
+        assert!(matches!(escape_attr_value("it's", '"'), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_attr_value_single_quoted() {
+        assert_eq!(
+            escape_attr_value(r#"say "hi" & it's"#, '\''),
+            r#"say "hi" &amp; it&#39;s"#
+        );
+        assert!(matches!(
+            escape_attr_value("\"quoted\"", '\''),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_attr_value_unknown_delimiter() {
+        assert_eq!(escape_attr_value("\"'", '`'), "&#34;&#39;");
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("&<>\"'"), "&amp;&lt;&gt;&#34;&#39;");
+        assert_eq!(escape_html("日本<語>"), "日本&lt;語&gt;");
+        assert!(matches!(escape_html("safe"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_audit_html() {
+        let audit = audit_html("<a href='x'>&amp; &#60;&bogus; ok</a>");
+        let escaped: Vec<(usize, char)> =
+            audit.escapes.iter().map(|f| (f.position, f.ch)).collect();
+        assert_eq!(
+            escaped,
+            vec![
+                (0, '<'),
+                (8, '\''),
+                (10, '\''),
+                (11, '>'),
+                (12, '&'),
+                (18, '&'),
+                (23, '&'),
+                (33, '<'),
+                (36, '>'),
+            ]
+        );
+        assert_eq!(audit.escapes[4].replacement, "&amp;");
+        assert_eq!(
+            audit.entities,
+            vec![
+                EntityFinding {
+                    position: 12,
+                    entity: "&amp;".to_string(),
+                    decoded: "&".to_string(),
+                },
+                EntityFinding {
+                    position: 18,
+                    entity: "&#60;".to_string(),
+                    decoded: "<".to_string(),
+                },
+            ]
+        );
+        assert!(!audit.is_clean());
+        assert!(audit_html("nothing here").is_clean());
+    }
+
+    #[test]
+    fn test_escape_html_to_matches_escape_html() {
+        for input in ["", "plain", "<b>&\"'</b>", "日本<語>", "tail&"] {
+            let mut out = String::from("prefix:");
+            escape_html_to(input, &mut out).unwrap();
+            assert_eq!(out, format!("prefix:{}", escape_html(input)));
+        }
+    }
+
+    #[test]
+    fn test_escape_html_to_single_write_when_safe() {
+        struct CountingWriter(usize, String);
+        impl fmt::Write for CountingWriter {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 += 1;
+                self.1.push_str(s);
+                Ok(())
+            }
+        }
+
+        let mut out = CountingWriter(0, String::new());
+        escape_html_to("nothing to escape", &mut out).unwrap();
+        assert_eq!(out.0, 1);
+        assert_eq!(out.1, "nothing to escape");
+    }
+
+    #[test]
+    fn test_escape_html_into_string_reuses_buffer() {
+        let inputs = ["plain", "<b>&\"'</b>", "", "日本<語>"];
+        let mut buf = String::new();
+        for i in 0..1000 {
+            let input = inputs[i % inputs.len()];
+            buf.clear();
+            escape_html_into_string(input, &mut buf);
+            assert_eq!(buf, escape_html(input));
+        }
+    }
+
+    #[test]
+    fn test_escape_html_into_string_appends() {
+        let mut buf = String::from("<p>");
+        escape_html_into_string("a & b", &mut buf);
+        buf.push_str("</p>");
+        assert_eq!(buf, "<p>a &amp; b</p>");
+    }
+
+    #[test]
+    fn test_escape_html_bytes() {
+        assert_eq!(escape_html_bytes(b"<a & b>"), b"&lt;a &amp; b&gt;");
+        assert_eq!(escape_html_bytes(b"\xff<\xfe"), b"\xff&lt;\xfe");
+        assert_eq!(
+            escape_html_bytes("日本<語>".as_bytes()),
+            escape_html("日本<語>").as_bytes()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_escape_html_bytes_to_stream() {
+        let input = b"<p>\xff & \"quoted\"</p>".repeat(1000);
+        let mut out = io::BufWriter::new(Vec::new());
+        let processed = escape_html_bytes_to(&input, &mut out).unwrap();
+        assert_eq!(processed, input.len());
+        assert_eq!(out.into_inner().unwrap(), escape_html_bytes(&input));
+    }
+
+    #[test]
+    fn test_needs_escaping() {
+        assert!(!needs_escaping(""));
+        assert!(!needs_escaping("plain 日本語 text"));
+        for special in ["&", "<", ">", "\"", "'"] {
+            assert!(needs_escaping(special));
+            assert!(needs_escaping(&format!("safe{}safe", special)));
+        }
+    }
+
+    #[test]
+    fn test_unescape_html5_named() {
+        assert_eq!(
+            unescape_html("&copy; &nbsp;&mdash; &hearts; &AElig;"),
+            "\u{a9} \u{a0}\u{2014} \u{2665} \u{c6}"
+        );
+        assert_eq!(unescape_html("&NotEqualTilde;"), "\u{2242}\u{338}");
+        assert_eq!(unescape_html("&bne;").chars().count(), 2);
+        assert_eq!(unescape_html("&Cross; &copy;"), "\u{2a2f} \u{a9}");
+        assert_eq!(unescape_html("&bogus; &Bogus"), "&bogus; &Bogus");
+    }
+
+    #[test]
+    fn test_entity_table_sorted() {
+        assert!(entities::NAMED_ENTITIES
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(entities::NAMED_ENTITIES.len(), 2125);
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("it's <x>"), "it&apos;s &lt;x&gt;");
+        assert_eq!(escape_xml("&\""), "&amp;&#34;");
+        assert!(matches!(escape_xml("plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_escape_xml_roundtrip() {
+        for input in ["it's", "<a href='x'>&\"</a>", "日本'語'", ""] {
+            assert_eq!(unescape_html(&escape_xml(input)), input);
+        }
+    }
+
+    #[test]
+    fn test_escape_attribute_whitespace() {
+        assert_eq!(
+            escape_attribute("a\tb\nc\rd\x0Ce"),
+            "a&#9;b&#10;c&#13;d&#12;e"
+        );
+        assert_eq!(escape_attribute("<'&\">"), "&lt;&#39;&amp;&#34;&gt;");
+        assert_eq!(escape_attribute("plain value"), "plain value");
+        assert_eq!(unescape_html(&escape_attribute("x\r\ny")), "x\r\ny");
+    }
+
+    #[test]
+    fn test_escape_js_string_script_breakout() {
+        assert_eq!(
+            escape_js_string("</script><script>alert(1)</script>"),
+            r"\x3C\/script\x3E\x3Cscript\x3Ealert(1)\x3C\/script\x3E"
+        );
+        assert!(!escape_js_string("</script>").contains("</"));
+    }
+
+    #[test]
+    fn test_escape_js_string_quotes_and_lines() {
+        assert_eq!(escape_js_string(r#"it's "q" \"#), r#"it\'s \"q\" \\"#);
+        assert_eq!(escape_js_string("a\nb\r\tc"), r"a\nb\r\tc");
+        assert_eq!(escape_js_string("a\u{2028}b\u{2029}c"), r"a\u2028b\u2029c");
+        assert_eq!(escape_js_string("nul\0 & 日本"), r"nul\x00 \x26 日本");
+    }
+
+    #[test]
+    fn test_escape_url_component() {
+        assert_eq!(escape_url_component("a b&c=d"), "a%20b%26c%3Dd");
+        assert_eq!(escape_url_component("Az09-._~"), "Az09-._~");
+        assert_eq!(escape_url_component("café"), "caf%C3%A9");
+        assert_eq!(escape_url_component("🌍"), "%F0%9F%8C%8D");
+        assert_eq!(escape_url_component("/path?q=<x>"), "%2Fpath%3Fq%3D%3Cx%3E");
+    }
+
+    #[test]
+    fn test_escape_css() {
+        assert_eq!(escape_css("red"), "red");
+        assert_eq!(escape_css("a;b"), r"a\3B b");
+        assert_eq!(escape_css("x;}"), r"x\3B\7D");
+        assert_eq!(escape_css("</style>"), r"\3C\2Fstyle\3E");
+        assert_eq!(escape_css("é"), r"\E9");
+    }
+
+    #[test]
+    fn test_escape_css_leading_digit_and_null() {
+        assert_eq!(escape_css("1st"), r"\31st");
+        assert_eq!(escape_css("1a"), r"\31 a");
+        assert_eq!(escape_css("12"), r"\31 2");
+        assert_eq!(escape_css("a1"), "a1");
+        assert_eq!(escape_css("a\0b"), r"a\FFFD b");
+        assert_eq!(escape_css("\0z"), r"\FFFDz");
+    }
+
+    #[test]
+    fn test_unescape_html_strict() {
+        assert_eq!(
+            unescape_html_strict("&lt;ok&gt; a & b").unwrap(),
+            "<ok> a & b"
+        );
+        assert_eq!(
+            unescape_html_strict("x &amp; &notanentity; &bogus;"),
+            Err(EscapeError::ProcessingError(
+                "unrecognized entity &notanentity; at byte 8".to_string()
+            ))
+        );
+        assert!(unescape_html_strict("&#xZZ;").is_err());
+        assert_eq!(unescape_html_strict("&amp &lt").unwrap(), "& <");
+        assert_eq!(unescape_html("&bogus;"), "&bogus;");
+    }
+
+    #[test]
+    fn test_unescape_bounded_entity_scan() {
+        let long_name = format!("&{};", "a".repeat(MAX_ENTITY_NAME_LEN + 1));
+        assert_eq!(unescape_html(&long_name), long_name);
+        assert_eq!(
+            unescape_html("&CounterClockwiseContourIntegral;"),
+            "\u{2233}"
+        );
+        let ampersands = "&".repeat(10_000);
+        assert_eq!(unescape_html(&ampersands), ampersands);
+        assert_eq!(unescape_html(&"&&amp;".repeat(1000)), "&&".repeat(1000));
+    }
+
+    #[test]
+    fn test_unescape_html_bytes_numeric() {
+        assert_eq!(unescape_html_bytes(b"&#60;b&#x3E;"), b"<b>");
+        assert_eq!(unescape_html_bytes(b"\xff&amp;\xfe"), b"\xff&\xfe");
+        assert_eq!(
+            unescape_html_bytes(b"&#xZZ; &#; &bogus;"),
+            b"&#xZZ; &#; &bogus;"
+        );
+        assert_eq!(unescape_html_bytes("&#233;".as_bytes()), "é".as_bytes());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_escape_html_to_bytesmut() {
+        let mut out = bytes::BytesMut::from("<p>");
+        for input in ["Tom & 'Jerry'", "plain 日本", "", "<\">"] {
+            let start = out.len();
+            escape_html_to_bytesmut(input, &mut out);
+            assert_eq!(&out[start..], escape_html(input).as_bytes());
+        }
+        assert_eq!(
+            &out[..],
+            "<p>Tom &amp; &#39;Jerry&#39;plain 日本&lt;&#34;&gt;".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_escape_html_split() {
+        let (prefix, rest) = escape_html_split("<b>hi");
+        assert_eq!((prefix, rest.as_ref()), ("", "&lt;b&gt;hi"));
+        assert!(matches!(rest, Cow::Owned(_)));
+        let (prefix, rest) = escape_html_split("日本 text 'q' & <x>");
+        assert_eq!(
+            (prefix, rest.as_ref()),
+            ("日本 text ", "&#39;q&#39; &amp; &lt;x&gt;")
+        );
+        assert!(matches!(rest, Cow::Owned(_)));
+        let (prefix, rest) = escape_html_split("no specials 🔥");
+        assert_eq!(prefix, "no specials 🔥");
+        assert!(matches!(rest, Cow::Borrowed("")));
+        let (prefix, rest) = escape_html_split("");
+        assert_eq!(prefix, "");
+        assert!(matches!(rest, Cow::Borrowed("")));
+
+        for input in ["a\"b", "ab>", "'", "x&y<z"] {
+            let (prefix, rest) = escape_html_split(input);
+            assert!(input.starts_with(prefix));
+            assert_eq!(format!("{}{}", prefix, rest), escape_html(input));
+        }
+    }
+
+    #[test]
+    fn test_escape_html_bytes_checked() {
+        assert_eq!(
+            escape_html_bytes_checked("<日本> & \0".as_bytes()).unwrap(),
+            "&lt;日本&gt; &amp; \0".as_bytes()
+        );
+        assert_eq!(escape_html_bytes_checked(b"").unwrap(), b"");
+        // The last is `<b>` in EBCDIC.
+        for invalid in [&b"\xff<"[..], b"a\xc3", b"\xed\xa0\x80", b"\x4c\xc2\x6e"] {
+            assert_eq!(
+                escape_html_bytes_checked(invalid),
+                Err(EscapeError::InvalidUtf8)
+            );
+        }
+    }
+
+    #[test]
+    fn test_escape_silent() {
+        assert_eq!(escape_silent(None), "");
+        assert!(matches!(escape_silent(Some("")), Cow::Borrowed("")));
+        assert_eq!(escape_silent(Some("<x>")), "&lt;x&gt;");
+        assert_eq!(crate::escape_silent(Some("'")), "&#39;");
+    }
+
+    #[test]
+    fn test_escape_html_into_slice() {
+        let input = "<b>Tom & 'Jerry'</b> 日本";
+        let expected = escape_html(input);
+
+        let mut exact = vec![0; expected.len()];
+        assert_eq!(
+            escape_html_into_slice(input, &mut exact),
+            Ok(expected.len())
+        );
+        assert_eq!(exact, expected.as_bytes());
+
+        let mut roomy = [b'.'; 128];
+        assert_eq!(
+            escape_html_into_slice(input, &mut roomy),
+            Ok(expected.len())
+        );
+        assert_eq!(&roomy[..expected.len()], expected.as_bytes());
+        assert!(roomy[expected.len()..].iter().all(|&b| b == b'.'));
+
+        let mut small = [b'.'; 8];
+        assert_eq!(
+            escape_html_into_slice(input, &mut small),
+            Err(expected.len())
+        );
+        assert_eq!(small, [b'.'; 8]);
+
+        assert_eq!(escape_html_into_slice("", &mut []), Ok(0));
+        assert_eq!(escape_html_into_slice("&", &mut []), Err(5));
+    }
+
+    #[test]
+    fn test_unescape_html_with_table() {
+        let table = EntityTable::new().insert("foo", "BAR");
+        assert_eq!(
+            unescape_html_with_table("a &foo; b &lt;&#33; &foo", &table),
+            "a BAR b <! &foo"
+        );
+
+        let input = "&lt; &amp &copy; &NotEqualTilde; &bogus;";
+        assert_eq!(
+            unescape_html_with_table(input, &EntityTable::default()),
+            unescape_html(input)
+        );
+
+        let table = EntityTable::new().remove("amp").insert("lt", "LT");
+        assert_eq!(
+            unescape_html_with_table("&amp; &amp &lt; &lt &gt", &table),
+            "&amp; &amp LT &lt >"
+        );
+        assert!(matches!(
+            unescape_html_with_table("&foo;", &EntityTable::new()),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_unescape_null_reference() {
+        for reference in ["&#0;", "&#x0;", "&#X0;", "&#00000000;", "&#x00;"] {
+            assert_eq!(unescape_html(reference), "\u{FFFD}", "{}", reference);
+            assert_eq!(
+                unescape_html_bytes(reference.as_bytes()),
+                "\u{FFFD}".as_bytes()
+            );
+            assert_eq!(unescape_numeric_only(reference), "\u{FFFD}");
+        }
+        assert_eq!(unescape_html_full("a&#0b&#x0"), "a\u{FFFD}b\u{FFFD}");
+        assert!(!unescape_html("&#0;&#x0;").contains('\0'));
+    }
+
+    #[test]
+    fn test_unescape_named_whitespace() {
+        assert_eq!(unescape_html("a&Tab;b&NewLine;c"), "a\tb\nc");
+        assert_eq!(unescape_html_bytes(b"&Tab;&NewLine;"), b"\t\n");
+        assert_eq!(unescape_html_strict("&Tab;&NewLine;").unwrap(), "\t\n");
+        assert_eq!(unescape_entities("&Tab;", EntitySet::Xml), "&Tab;");
+        // Not legacy names, so the `;` is required.
+        assert_eq!(unescape_html("&Tab &NewLine"), "&Tab &NewLine");
+        assert_eq!(unescape_html("&tab;"), "&tab;");
+        // Decoded whitespace is ordinary text to the control-character checks.
+        let strict = EscapeOptions::new().control_chars(ControlPolicy::Error);
+        assert!(try_escape_html_with(&unescape_html("&Tab;&NewLine;"), &strict).is_ok());
+    }
+
+    #[test]
+    fn test_escape_html_const() {
+        const ESCAPED: &str = crate::escape_html_const!("<a href=\"x\">Tom & 'Jerry'</a> 日本");
+        const EMPTY: &str = crate::escape_html_const!("");
+        const LEN: usize = escaped_html_len("<&>");
+        assert_eq!(ESCAPED, escape_html("<a href=\"x\">Tom & 'Jerry'</a> 日本"));
+        assert_eq!(EMPTY, "");
+        assert_eq!(LEN, 13);
+        assert_eq!(&escape_html_array::<6>("a<b"), b"a&lt;b");
+    }
+
+    #[test]
+    #[should_panic(expected = "escaped_html_len")]
+    fn test_escape_html_array_wrong_len() {
+        escape_html_array::<3>("a<b");
+    }
+
+    #[test]
+    fn test_escape_gt_false() {
+        let no_gt = EscapeOptions::new().escape_gt(false);
+        assert!(matches!(
+            escape_html_with("a > b", &no_gt),
+            Cow::Borrowed("a > b")
+        ));
+        assert_eq!(
+            escape_html_with("<a> & \"b\" 'c' >", &no_gt),
+            "&lt;a> &amp; &#34;b&#34; &#39;c&#39; >"
+        );
+        assert_eq!(
+            escape_html_with("a > b", &EscapeOptions::default()),
+            "a &gt; b"
+        );
+        assert_eq!(escape_html("a > b"), "a &gt; b");
+    }
+
+    #[test]
+    fn test_unescape_html_bytes_astral() {
+        for (reference, ch) in [
+            ("&#x1F30D;", '\u{1F30D}'),
+            ("&#127757;", '\u{1F30D}'),
+            ("&#x10000;", '\u{10000}'),
+            ("&#x10FFFF;", '\u{10FFFF}'),
+            ("&#X1d11e;", '\u{1D11E}'),
+        ] {
+            let decoded = unescape_html_bytes(reference.as_bytes());
+            assert_eq!(
+                decoded,
+                unescape_html(reference).as_bytes(),
+                "{}",
+                reference
+            );
+            assert_eq!(decoded, ch.to_string().as_bytes(), "{}", reference);
+            assert_eq!(decoded.len(), 4, "{}", reference);
+        }
+        assert_eq!(
+            unescape_html_bytes(b"\xff&#x1F30D;\xfe"),
+            b"\xff\xf0\x9f\x8c\x8d\xfe"
+        );
+    }
+
+    #[test]
+    fn test_unescape_html_bytes_surrogates() {
+        for reference in ["&#xD800;", "&#xDFFF;", "&#55296;", "&#xdbff;"] {
+            assert_eq!(
+                unescape_html_bytes(reference.as_bytes()),
+                b"\xef\xbf\xbd",
+                "{}",
+                reference
+            );
+            assert_eq!(unescape_html(reference), "\u{FFFD}");
+        }
+    }
+
+    #[test]
+    fn test_unescape_html_bytes_parity() {
+        let inputs = [
+            "",
+            "plain",
+            "&lt;&gt;&amp;&quot;&apos;",
+            "&#60;&#x3C;&#X3c;&#39;&#x27;",
+            "a & b &amp c &bogus; &#xZZ; &#;",
+            "&&&amp;&&",
+            "&copy;&nbsp;&NotEqualTilde;",
+            "&#0; &#99999999999;",
+        ];
+        for input in inputs {
+            assert_eq!(
+                unescape_html_bytes(input.as_bytes()),
+                unescape_html(input).as_bytes(),
+                "input: {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_escape_options_default_matches_escape_html() {
+        let opts = EscapeOptions::default();
+        for input in ["", "plain", "&<>\"'/", "日本<語>", "a/b"] {
+            assert_eq!(escape_html_with(input, &opts), escape_html(input));
+        }
+        assert!(matches!(escape_html_with("a/b", &opts), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_escape_options_builder() {
+        let hex = EscapeOptions::new().quote_style(QuoteStyle::Hex);
+        assert_eq!(escape_html_with("\"'", &hex), "&#x22;&#x27;");
+
+        let named = EscapeOptions::new().quote_style(QuoteStyle::Named);
+        assert_eq!(escape_html_with("\"'", &named), "&quot;&#x27;");
+
+        let no_gt = EscapeOptions::new().escape_gt(false);
+        assert_eq!(escape_html_with("<a> > b", &no_gt), "&lt;a> > b");
+
+        let slash = EscapeOptions::new().escape_slash(true);
+        assert_eq!(escape_html_with("</p>", &slash), "&lt;&#47;p&gt;");
+        assert_eq!(escape_html_with("a/b", &slash), "a&#47;b");
+    }
+
+    #[test]
+    fn test_escape_html_bytes_cow() {
+        assert!(matches!(
+            escape_html_bytes_cow(b"safe \xff bytes"),
+            Cow::Borrowed(_)
+        ));
+        let escaped = escape_html_bytes_cow(b"<\xff>");
+        assert!(matches!(escaped, Cow::Owned(_)));
+        assert_eq!(escaped.as_ref(), b"&lt;\xff&gt;");
+    }
+
+    #[test]
+    fn test_strip_tags() {
+        assert_eq!(strip_tags("<b>a  b</b> &amp; c"), "a b & c");
+        assert_eq!(
+            strip_tags("  <p>Hello\n\t<em>World</em></p>  "),
+            "Hello World"
+        );
+        assert_eq!(strip_tags("a<!-- <b>x</b> -->b"), "ab");
+        assert_eq!(strip_tags("unclosed <tag and"), "unclosed <tag and");
+        assert_eq!(strip_tags("&lt;not a tag&gt;"), "<not a tag>");
+    }
+
+    #[test]
+    fn test_escape_options_decimal_only() {
+        let opts = EscapeOptions::new()
+            .quote_style(QuoteStyle::Decimal)
+            .escape_slash(true);
+        let escaped = escape_html_with("<a href='/x'>\"it's\" & </a>", &opts);
+        assert!(!escaped.contains("&#x"));
+        assert_eq!(
+            escaped,
+            "&lt;a href=&#39;&#47;x&#39;&gt;&#34;it&#39;s&#34; &amp; &lt;&#47;a&gt;"
+        );
+        assert!(!escape_html("'\"").contains("&#x"));
+    }
+
+    #[test]
+    fn test_escape_chars_subset() {
+        assert_eq!(
+            escape_chars("<a href=\"x\">Tom & 'Jerry'</a>", &['<', '&']),
+            "&lt;a href=\"x\">Tom &amp; 'Jerry'&lt;/a>"
+        );
+        assert_eq!(escape_chars("a/b<", &['/', '<']), "a/b&lt;");
+        assert!(matches!(escape_chars("<>", &[]), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_escape_chars_all_five() {
+        let all = ['&', '<', '>', '"', '\''];
+        for input in ["", "plain", "&<>\"'", "日本<語>"] {
+            assert_eq!(escape_chars(input, &all), escape_html(input));
+        }
+    }
+
+    #[test]
+    fn test_unescaper_split_entities() {
+        let mut unescaper = Unescaper::new();
+        let mut out = unescaper.push("&am");
+        out += &unescaper.push("p; &l");
+        out += &unescaper.push("t;");
+        out += &unescaper.finish();
+        assert_eq!(out, "& <");
+    }
+
+    #[test]
+    fn test_unescaper_holds_back_trailing_ampersand() {
+        let mut unescaper = Unescaper::new();
+        assert_eq!(unescaper.push("x&"), "x");
+        assert_eq!(unescaper.push("amp;y"), "&y");
+        assert_eq!(unescaper.finish(), "");
+
+        // `&am` alone would stay literal, `&amp` would decode without `;`.
+        let mut unescaper = Unescaper::new();
+        assert_eq!(unescaper.push("...&am"), "...");
+        assert_eq!(unescaper.push("p"), "");
+        assert_eq!(unescaper.push(";!"), "&!");
+
+        let mut unescaper = Unescaper::new();
+        assert_eq!(unescaper.push("tail &"), "tail ");
+        assert_eq!(unescaper.finish(), "&");
+    }
+
+    #[test]
+    fn test_unescaper_matches_unescape_html() {
+        let input = "a &amp; b &#x3C;c&#62; &copy; &bogus; & &NotEqualTilde; end&";
+        for size in 1..input.len() {
+            let mut unescaper = Unescaper::new();
+            let mut out = String::new();
+            let mut rest = input;
+            while !rest.is_empty() {
+                let mut cut = size.min(rest.len());
+                while !rest.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                out += &unescaper.push(&rest[..cut]);
+                rest = &rest[cut..];
+            }
+            out += &unescaper.finish();
+            assert_eq!(out, unescape_html(input), "chunk size {}", size);
+        }
+    }
+
+    #[test]
+    fn test_escaped_len_exact() {
+        let inputs = [
+            String::new(),
+            "plain".to_string(),
+            "&<>\"'".to_string(),
+            "日本<語>🔥&".to_string(),
+            "'".repeat(100),
+            "a<b".repeat(37),
+        ];
+        for input in &inputs {
+            let escaped = escape_html(input);
+            assert_eq!(escaped.len(), escaped_len(input), "input: {:?}", input);
+            if let Cow::Owned(s) = escaped {
+                assert_eq!(s.capacity(), s.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_escape_html_owasp() {
+        assert_eq!(escape_html_owasp("</script>"), "&lt;&#x2F;script&gt;");
+        assert_eq!(escape_html_owasp("'\"&"), "&#x27;&#x22;&amp;");
+        assert_eq!(escape_html("</script>"), "&lt;/script&gt;");
+    }
+
+    #[test]
+    fn test_unescape_legacy_without_semicolon() {
+        assert_eq!(unescape_html("&lt&gt&amp&quot"), "<>&\"");
+        assert_eq!(unescape_html("a &amp b"), "a & b");
+        assert_eq!(unescape_html("&copy 2024 &COPY"), "\u{a9} 2024 \u{a9}");
+        assert_eq!(unescape_html("&gt3 &amp=x"), ">3 &=x");
+        // Only the longest legacy prefix decodes; the rest stays literal.
+        assert_eq!(unescape_html("&amphitheater"), "&hitheater");
+        assert_eq!(unescape_html("&notit; &notin;"), "\u{ac}it; \u{2209}");
+        // Names without a legacy form still need the semicolon.
+        assert_eq!(unescape_html("&apos &hearts"), "&apos &hearts");
+        assert_eq!(unescape_html("&l &a"), "&l &a");
+    }
+
+    #[test]
+    fn test_unescape_numeric_remapping() {
+        assert_eq!(unescape_html("&#128;"), "\u{20ac}");
+        assert_eq!(
+            unescape_html("&#x80;&#x99;&#159;"),
+            "\u{20ac}\u{2122}\u{178}"
+        );
+        assert_eq!(unescape_html("&#x81;&#x9D;"), "\u{81}\u{9d}");
+        assert_eq!(unescape_html("&#127;&#160;"), "\u{7f}\u{a0}");
+        assert_eq!(unescape_html("&#xD800;&#57343;"), "\u{fffd}\u{fffd}");
+        assert_eq!(unescape_html("&#x110000;"), "\u{fffd}");
+        assert_eq!(
+            unescape_html_bytes(b"&#128;&#xDFFF;"),
+            "\u{20ac}\u{fffd}".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_unescape_long_name_run() {
+        let input = format!("&{}", "a".repeat(5_000_000));
+        assert!(matches!(unescape_html(&input), Cow::Borrowed(_)));
+        assert_eq!(unescape_html_bytes(input.as_bytes()), input.as_bytes());
+        assert_eq!(unescape_html(&format!("{};", input)), format!("{};", input));
+    }
+
+    #[test]
+    fn test_escape_multibyte_boundaries() {
+        for input in [
+            "é<",
+            "<é",
+            "日&本",
+            "🔥'🔥\"",
+            "\u{80}>\u{7ff}<\u{800}&\u{ffff}'\u{10000}",
+        ] {
+            let expected: String = input
+                .chars()
+                .map(|ch| html_entity(ch).map_or_else(|| ch.to_string(), str::to_string))
+                .collect();
+            assert_eq!(escape_html(input), expected);
+            assert_eq!(escape_xml(input), expected.replace("&#39;", "&apos;"));
+        }
+    }
+
+    #[test]
+    fn test_entry_points_agree_on_quotes() {
+        let input = "'\"";
+        let expected = "&#39;&#34;";
+        let mut written = String::new();
+        escape_html_to(input, &mut written).unwrap();
+        let mut appended = String::new();
+        escape_html_into_string(input, &mut appended);
+
+        assert_eq!(escape_html(input), expected);
+        assert_eq!(escape_html_with(input, &EscapeOptions::default()), expected);
+        assert_eq!(written, expected);
+        assert_eq!(appended, expected);
+        assert_eq!(escape_chars(input, &['\'', '"']), expected);
+        assert_eq!(escape_attribute(input), expected);
+        assert_eq!(escape_html_bytes(input.as_bytes()), expected.as_bytes());
+        assert_eq!(
+            escape_html_bytes_cow(input.as_bytes()).as_ref(),
+            expected.as_bytes()
+        );
+        assert_eq!(rysafe_core::escape(input), expected);
+        assert_eq!(format!("{}", crate::escaped::HtmlEscaped(input)), expected);
+    }
+
+    #[test]
+    fn test_control_chars_pass() {
+        let opts = EscapeOptions::new().control_chars(ControlPolicy::Pass);
+        assert_eq!(escape_html_with("a\x01<\x7f", &opts), "a\x01&lt;\x7f");
+        assert_eq!(try_escape_html_with("\x1b", &opts).unwrap(), "\x1b");
+        assert_eq!(escape_html("\x01"), "\x01");
+    }
+
+    #[test]
+    fn test_control_chars_error() {
+        let opts = EscapeOptions::new().control_chars(ControlPolicy::Error);
+        assert_eq!(
+            try_escape_html_with("ok<\x08", &opts),
+            Err(EscapeError::ControlCharacter {
+                position: 3,
+                ch: '\x08'
+            })
+        );
+        assert_eq!(
+            try_escape_html_with("tab\tline\r\n<", &opts).unwrap(),
+            "tab\tline\r\n&lt;"
+        );
+    }
+
+    #[test]
+    fn test_control_chars_encode() {
+        let opts = EscapeOptions::new().control_chars(ControlPolicy::Encode);
+        assert_eq!(
+            escape_html_with("a\x01b\x00\x1f\x7f\t\n\r", &opts),
+            "a&#1;b&#0;&#31;&#127;\t\n\r"
+        );
+        let hex = opts.clone().quote_style(QuoteStyle::Hex);
+        assert_eq!(escape_html_with("\x0b\x1b\x7f", &hex), "&#xB;&#x1B;&#x7F;");
+        assert_eq!(
+            escape_html_with("\x01<", &opts).len(),
+            opts.escaped_len("\x01<")
+        );
+        assert_eq!(
+            unescape_html(&escape_html_with("\x02x\x03", &opts)),
+            "\x02x\x03"
+        );
+    }
+
+    #[test]
+    fn test_try_escape_html_with_limit_boundary() {
+        let at_max = "<".repeat(16);
+        assert_eq!(
+            try_escape_html_with_limit(&at_max, 16).unwrap(),
+            "&lt;".repeat(16)
+        );
+        assert_eq!(
+            try_escape_html_with_limit(&format!("{}x", at_max), 16),
+            Err(EscapeError::InputTooLarge { len: 17, max: 16 })
+        );
+        assert_eq!(try_escape_html_with_limit("", 0).unwrap(), "");
+        assert!(try_escape_html_with_limit("a", 0).is_err());
+        // The limit counts input bytes, not escaped output or chars.
+        assert!(try_escape_html_with_limit("日本", 6).is_ok());
+        assert!(try_escape_html_with_limit("日本", 5).is_err());
+    }
+
+    #[test]
+    fn test_try_escape_html_default_limit() {
+        let at_max = "a".repeat(MAX_INPUT_SIZE);
+        assert!(matches!(try_escape_html(&at_max), Ok(Cow::Borrowed(_))));
+        let over = "a".repeat(MAX_INPUT_SIZE + 1);
+        assert_eq!(
+            try_escape_html(&over),
+            Err(EscapeError::InputTooLarge {
+                len: MAX_INPUT_SIZE + 1,
+                max: MAX_INPUT_SIZE
+            })
+        );
+        assert!(try_escape_html_with_limit(&over, 50 * 1024 * 1024).is_ok());
+    }
+
+    #[test]
+    fn test_try_escape_html_nul() {
+        assert_eq!(
+            try_escape_html_nul("a\0b", NulPolicy::Reject),
+            Err(EscapeError::NulByte { position: 1 })
+        );
+        assert_eq!(try_escape_html_nul("a\0b", NulPolicy::Strip).unwrap(), "ab");
+        assert_eq!(
+            try_escape_html_nul("<\0>\0", NulPolicy::Strip).unwrap(),
+            "&lt;&gt;"
+        );
+        assert_eq!(
+            try_escape_html_nul("a<b", NulPolicy::Reject).unwrap(),
+            "a&lt;b"
+        );
+        assert_eq!(escape_html("a\0b"), "a\0b");
+    }
+
+    #[test]
+    fn test_unescape_html_full() {
+        assert_eq!(unescape_html_full("&bne;"), "=\u{20e5}");
+        assert_eq!(unescape_html_full("&bne;").chars().count(), 2);
+        assert_eq!(
+            unescape_html_full("&NotEqualTilde; &amp"),
+            "\u{2242}\u{338} &"
+        );
+        assert_eq!(unescape_html_full("a&#60b &#x3Cp>"), "a<b <p>");
+        assert_eq!(unescape_html_full("&#x3Cfoo"), "\u{3cf}oo");
+        assert_eq!(
+            unescape_html_full("&#128 &#; &#x; &# x"),
+            "\u{20ac} &#; &#x; &# x"
+        );
+        assert_eq!(unescape_html("a&#60b"), "a&#60b");
+    }
+
+    #[test]
+    fn test_escape_html_preserve_entities() {
+        assert_eq!(escape_html_preserve_entities("a & b"), "a &amp; b");
+        assert!(matches!(
+            escape_html_preserve_entities("a &amp; b"),
+            Cow::Borrowed("a &amp; b")
+        ));
+        assert_eq!(
+            escape_html_preserve_entities("a &notreal; b"),
+            "a &amp;notreal; b"
+        );
+        assert_eq!(
+            escape_html_preserve_entities("&copy; &#39;x&#x27; &amp <b>"),
+            "&copy; &#39;x&#x27; &amp;amp &lt;b&gt;"
+        );
+        assert_eq!(
+            escape_html_preserve_entities("&#xZZ; &"),
+            "&amp;#xZZ; &amp;"
+        );
+        for input in ["", "plain", "<'\">", "a &amp; b & c &lt;"] {
+            let once = escape_html_preserve_entities(input).into_owned();
+            assert_eq!(escape_html_preserve_entities(&once), once);
+        }
+    }
+
+    #[test]
+    fn test_escape_html_lines() {
+        let lines: Vec<_> = escape_html_lines("a<b\r\nc&d\n\nlast").collect();
+        assert_eq!(lines, ["a&lt;b\r\n", "c&amp;d\n", "\n", "last"]);
+        assert!(matches!(lines[2], Cow::Borrowed(_)));
+
+        let trailing: Vec<_> = escape_html_lines("<x>\n").collect();
+        assert_eq!(trailing, ["&lt;x&gt;\n"]);
+        assert_eq!(
+            escape_html_lines("no newline").collect::<Vec<_>>(),
+            ["no newline"]
+        );
+        assert_eq!(escape_html_lines("").count(), 0);
+
+        for input in ["x\r\n<y>\r\n", "'\n\"\n", "one"] {
+            assert_eq!(
+                escape_html_lines(input).collect::<String>(),
+                escape_html(input)
+            );
+        }
+    }
+
+    #[test]
+    fn test_escape_html_cow() {
+        assert!(matches!(
+            escape_html_cow(Cow::Borrowed("safe")),
+            Cow::Borrowed("safe")
+        ));
+        assert_eq!(escape_html_cow(Cow::Borrowed("<b>")), "&lt;b&gt;");
+
+        let owned = String::from("already owned");
+        let ptr = owned.as_ptr();
+        match escape_html_cow(Cow::Owned(owned)) {
+            Cow::Owned(kept) => assert_eq!(kept.as_ptr(), ptr),
+            Cow::Borrowed(_) => panic!("owned input came back borrowed"),
+        }
+        let escaped = escape_html_cow(Cow::Owned(String::from("a & b")));
+        assert!(matches!(escaped, Cow::Owned(_)));
+        assert_eq!(escaped, "a &amp; b");
+    }
+
+    #[test]
+    fn test_escape_html_ascii() {
+        assert_eq!(escape_html_ascii("café"), "caf&#xE9;");
+        assert_eq!(escape_html_ascii("🌍"), "&#x1F30D;");
+        assert_eq!(
+            escape_html_ascii("<日本> & 'x'"),
+            "&lt;&#x65E5;&#x672C;&gt; &amp; &#39;x&#39;"
+        );
+        assert_eq!(
+            escape_html_ascii("\u{80}\u{9f}\u{a0}plain"),
+            "&#xFFFD;&#xFFFD;&#xA0;plain"
+        );
+        assert!(escape_html_ascii("mixed 🔥 ü 😀").is_ascii());
+        assert_eq!(
+            unescape_html(&escape_html_ascii("naïve 🌍 <x>")),
+            "naïve 🌍 <x>"
+        );
+    }
+
+    #[test]
+    fn test_escape_html_ascii_roundtrip() {
+        let chars = (0..0x3000).chain([0xFFFD, 0xFFFF, 0x1F30D, 0x10FFFF]);
+        for ch in chars.filter_map(char::from_u32) {
+            let text = ch.to_string();
+            let back = unescape_html(&escape_html_ascii(&text)).into_owned();
+            if ('\u{80}'..='\u{9f}').contains(&ch) {
+                assert_eq!(back, "\u{FFFD}", "U+{:04X}", ch as u32);
+            } else {
+                assert_eq!(back, text, "U+{:04X}", ch as u32);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unescape_surrogate_references() {
+        for input in ["&#xD800;", "&#XdbFF;", "&#55296;", "&#xDFFF;"] {
+            assert_eq!(unescape_html(input), "\u{fffd}", "input: {:?}", input);
+            assert_eq!(unescape_html_bytes(input.as_bytes()), "\u{fffd}".as_bytes());
+            assert_eq!(unescape_html_strict(input).unwrap(), "\u{fffd}");
+            assert_eq!(unescape_html_full(input), "\u{fffd}");
+        }
+        assert_eq!(unescape_html_full("&#xD83D"), "\u{fffd}");
+        let mut unescaper = Unescaper::new();
+        let out = unescaper.push("a&#xD8") + &unescaper.push("00;b") + &unescaper.finish();
+        assert_eq!(out, "a\u{fffd}b");
+        assert_eq!(audit_html("&#xD800;").entities[0].decoded, "\u{fffd}");
+    }
+
+    #[test]
+    fn test_escape_attr_quoted_minimal() {
+        assert_eq!(
+            escape_attr_double_quoted(r#"<a> & "b" 'c'"#),
+            "<a> &amp; &#34;b&#34; 'c'"
+        );
+        assert_eq!(
+            escape_attr_single_quoted(r#"<a> & "b" 'c'"#),
+            r#"<a> &amp; "b" &#39;c&#39;"#
+        );
+        assert!(matches!(
+            escape_attr_double_quoted("<script>'x'</script>"),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            escape_attr_single_quoted("<b>\"x\"</b>"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_escape_many() {
+        let inputs = [
+            "plain",
+            "<b>",
+            "",
+            "Tom & 'Jerry'",
+            "日本<語>",
+            "safe again",
+        ];
+        let escaped = escape_many(&inputs);
+        assert_eq!(escaped.len(), inputs.len());
+        for (input, out) in inputs.iter().zip(&escaped) {
+            assert_eq!(out, &escape_html(input));
+            assert_eq!(matches!(out, Cow::Borrowed(_)), !needs_escaping(input));
+        }
+        assert!(escape_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_unescape_numeric_only() {
+        assert_eq!(unescape_numeric_only("&amp;"), "&amp;");
+        assert_eq!(unescape_numeric_only("&#38;"), "&");
+        assert_eq!(
+            unescape_numeric_only("&lt;b&gt; &#60;i&#x3E; &amp &copy; &#128;"),
+            "&lt;b&gt; <i> &amp &copy; \u{20ac}"
+        );
+        assert_eq!(unescape_numeric_only("&#38;amp;"), "&amp;");
+        assert!(matches!(
+            unescape_numeric_only("&amp;&lt;"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_unescape_html_require_semicolon() {
+        assert_eq!(unescape_html("&lt x"), "< x");
+        assert_eq!(unescape_html_require_semicolon("&lt x"), "&lt x");
+        assert_eq!(unescape_html_require_semicolon("&lt; x"), "< x");
+        assert_eq!(
+            unescape_html_require_semicolon("&amp &notin; &notit; &#60; &#60"),
+            "&amp \u{2209} &notit; < &#60"
+        );
+        assert!(matches!(
+            unescape_html_require_semicolon("AT&T &copy"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_unescape_entities_xml() {
+        assert_eq!(
+            unescape_entities("&copy; 2024", EntitySet::Xml),
+            "&copy; 2024"
+        );
+        assert_eq!(
+            unescape_entities("&copy; 2024", EntitySet::Html5),
+            "\u{a9} 2024"
+        );
+        assert_eq!(
+            unescape_entities("&lt;&gt;&amp;&quot;&apos; &#169; &#xA9;", EntitySet::Xml),
+            "<>&\"' \u{a9} \u{a9}"
+        );
+        assert_eq!(
+            unescape_entities("&lt &amp &nbsp;", EntitySet::Xml),
+            "&lt &amp &nbsp;"
+        );
+        assert!(matches!(
+            unescape_entities("&copy;", EntitySet::Xml),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_unescaper_xml_entities() {
+        let mut unescaper = Unescaper::with_entities(EntitySet::Xml);
+        let mut out = unescaper.push("&co");
+        out += &unescaper.push("py; &am");
+        out += &unescaper.push("p;");
+        out += &unescaper.finish();
+        assert_eq!(out, "&copy; &");
+    }
+
+    #[test]
+    fn test_unescape_apostrophe_reference_forms() {
+        for reference in ["&#39;", "&#x27;", "&#X27;", "&#039;", "&#x0027;"] {
+            assert_eq!(unescape_html(reference), "'", "{}", reference);
+            assert_eq!(unescape_html_bytes(reference.as_bytes()), b"'");
+            let (_, stats) = unescape_html_stats(reference);
+            assert_eq!((stats.named, stats.numeric), (0, 1), "{}", reference);
+        }
+        assert_eq!(unescape_html("&#X27"), "&#X27");
+        assert_eq!(unescape_html_full("&#X27"), "'");
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_escape_html_parallel() {
+        // Multibyte characters on both sides of every possible cut.
+        let input = "日本<語> & 'x' 🔥".repeat(PARALLEL_MIN_LEN / 8);
+        assert!(input.len() > PARALLEL_MIN_LEN);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(7)
+            .build()
+            .unwrap();
+        assert_eq!(
+            pool.install(|| escape_html_parallel(&input)),
+            escape_html(&input)
+        );
+        assert_eq!(escape_html_parallel("<b>"), "&lt;b&gt;");
+        assert_eq!(escape_html_parallel(""), "");
+    }
+
+    #[test]
+    fn test_sanitize_text() {
+        assert_eq!(sanitize_text("<b>\x07hi</b>"), "&lt;b&gt;hi&lt;/b&gt;");
+        assert_eq!(
+            sanitize_text("a\tb\r\nc\0d\x1be\x7ff\u{85}g & 日本"),
+            "a\tb\r\ncdefg &amp; 日本"
+        );
+        assert_eq!(sanitize_text(""), "");
+        assert_eq!(sanitize_text("\x01\x02"), "");
+        assert_eq!(sanitize_text("日本\u{9f}"), "日本");
+    }
+
+    #[test]
+    fn test_escape_html_counted() {
+        let (escaped, count) = escape_html_counted("<>&\"'");
+        assert_eq!(escaped, "&lt;&gt;&amp;&#34;&#39;");
+        assert_eq!(count, 5);
+
+        let (escaped, count) = escape_html_counted("safe");
+        assert!(matches!(escaped, Cow::Borrowed("safe")));
+        assert_eq!(count, 0);
+
+        assert_eq!(escape_html_counted("日本 <b> & 🔥").1, 3);
+    }
+
+    #[test]
+    fn test_double_escaped() {
+        assert!(is_double_escaped("&amp;lt;"));
+        assert_eq!(undo_double_escape("&amp;lt;"), "&lt;");
+        assert!(!is_double_escaped("&lt;"));
+        assert!(matches!(undo_double_escape("&lt;"), Cow::Borrowed("&lt;")));
+    }
+
+    #[test]
+    fn test_undo_double_escape_one_layer() {
+        assert_eq!(
+            undo_double_escape("&amp;#39;x&amp;#39; &lt;b&gt; AT&amp;T"),
+            "&#39;x&#39; &lt;b&gt; AT&amp;T"
+        );
+        assert_eq!(undo_double_escape("&amp;amp;lt;"), "&amp;lt;");
+        assert!(!is_double_escaped("&amp; &amp;lt &amp;bogus;"));
+        assert_eq!(
+            undo_double_escape(&escape_html(&escape_html("<a href='x'>&</a>"))),
+            escape_html("<a href='x'>&</a>")
+        );
+    }
+
+    #[test]
+    fn test_unescape_numeric_digit_cap() {
+        assert_eq!(unescape_html("&#x0000003C;&#00000060;"), "<<");
+        assert_eq!(
+            unescape_html("&#1114111;&#x10FFFF;"),
+            "\u{10ffff}\u{10ffff}"
+        );
+        for long in [
+            "&#x00000000000000000001;",
+            "&#000000060;",
+            "&#x000000003C;",
+            "&#99999999999;",
+        ] {
+            assert_eq!(unescape_html(long), long);
+            assert_eq!(unescape_html_bytes(long.as_bytes()), long.as_bytes());
+            assert_eq!(unescape_html_full(long), long);
+            assert!(unescape_html_strict(long).is_err());
+        }
+        assert_eq!(unescape_html_full("&#x000000003C"), "&#x000000003C");
+    }
+
+    #[test]
+    fn test_escape_html_capacity_large_input() {
+        let input = "<p class=\"x\">Tom & 'Jerry'</p> plain text ".repeat(25_000);
+        let Cow::Owned(escaped) = escape_html(&input) else {
+            panic!("input needs escaping");
+        };
+        assert_eq!(escaped.len(), escaped_len(&input));
+        assert_eq!(escaped.capacity(), escaped.len());
+        assert!(escaped.len() < input.len() * 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_escape_os_str() {
+        let name = OsStr::new("<a & b>.txt");
+        assert_eq!(escape_os_str(name), OsStr::new("&lt;a &amp; b&gt;.txt"));
+        assert!(matches!(
+            escape_os_str(OsStr::new("plain.txt")),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn test_escape_os_str_keeps_non_utf8() {
+        use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+        let name = OsStr::from_bytes(b"caf\xe9 <1>\xff.txt");
+        let escaped = escape_os_str(name).into_owned().into_vec();
+        assert_eq!(escaped, b"caf\xe9 &lt;1&gt;\xff.txt");
+    }
+}
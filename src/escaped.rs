@@ -0,0 +1,169 @@
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::escape::escape_html_bytes_to;
+use crate::escape::escape_html_to;
+
+/// Escapes the wrapped text when formatted, writing straight into the
+/// formatter: `write!(f, "{}", HtmlEscaped(user_input))`.
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlEscaped<'a>(pub &'a str);
+
+impl fmt::Display for HtmlEscaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        escape_html_to(self.0, f)
+    }
+}
+
+/// An `io::Write` that HTML-escapes every byte before passing it to `inner`:
+/// `write!(EscapeWriter::new(&mut file), "{}", untrusted)`.
+///
+/// Only ASCII bytes are ever replaced and they never occur inside a multibyte
+/// UTF-8 sequence, so a character split across two writes needs no buffering.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct EscapeWriter<W: io::Write> {
+    inner: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> EscapeWriter<W> {
+    pub fn new(inner: W) -> Self {
+        EscapeWriter { inner }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> io::Write for EscapeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        escape_html_bytes_to(buf, &mut self.inner)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Text that is HTML-escaped when serialized and unescaped when deserialized
+/// (with the `serde` feature).
+///
+/// The wrapped string is always the raw, unescaped text. Deserializing
+/// `serialize(x)` gives back `x`, but the reverse does not hold: decoding is
+/// lenient, so input such as `"AT&T"` or `"&amp"` keeps its bare ampersand
+/// and serializes again as `"AT&amp;T"` / `"&amp;amp"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EscapedString(pub String);
+
+impl From<String> for EscapedString {
+    fn from(text: String) -> Self {
+        EscapedString(text)
+    }
+}
+
+impl From<&str> for EscapedString {
+    fn from(text: &str) -> Self {
+        EscapedString(text.into())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EscapedString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&crate::escape::escape_html(&self.0))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EscapedString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(EscapedString(
+            crate::escape::unescape_html(&text).into_owned(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn test_html_escaped_display() {
+        assert_eq!(format!("<p>{}</p>", HtmlEscaped("<b>")), "<p>&lt;b&gt;</p>");
+        assert_eq!(format!("{}", HtmlEscaped("plain")), "plain");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_escape_writer() {
+        use std::io::Write;
+
+        let untrusted = "Tom & 'Jerry'";
+        let mut out = Vec::new();
+        write!(EscapeWriter::new(&mut out), "<p>{}</p>", untrusted).unwrap();
+        assert_eq!(out, b"&lt;p&gt;Tom &amp; &#39;Jerry&#39;&lt;/p&gt;");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_escape_writer_split_multibyte() {
+        use std::io::Write;
+
+        let input = "<日本🔥>".as_bytes();
+        for cut in 0..=input.len() {
+            let mut writer = EscapeWriter::new(Vec::new());
+            writer.write_all(&input[..cut]).unwrap();
+            writer.write_all(&input[cut..]).unwrap();
+            writer.flush().unwrap();
+            assert_eq!(
+                writer.into_inner(),
+                "&lt;日本🔥&gt;".as_bytes(),
+                "cut {}",
+                cut
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_escapes() {
+        let value = EscapedString::from("<b>\"Tom\" & 'Jerry'</b>");
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#""&lt;b&gt;&#34;Tom&#34; &amp; &#39;Jerry&#39;&lt;/b&gt;""#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_unescapes() {
+        let value: EscapedString = serde_json::from_str(r#""a &lt; b &amp;&amp; c""#).unwrap();
+        assert_eq!(value.0, "a < b && c");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_roundtrip() {
+        for text in ["", "plain", "<script>", "&amp;", "AT&T", "日本<語>"] {
+            let json = serde_json::to_string(&EscapedString::from(text)).unwrap();
+            let back: EscapedString = serde_json::from_str(&json).unwrap();
+            assert_eq!(back.0, text);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lenient_input_is_not_canonical() {
+        let value: EscapedString = serde_json::from_str(r#""AT&T""#).unwrap();
+        assert_eq!(value.0, "AT&T");
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#""AT&amp;T""#);
+    }
+}
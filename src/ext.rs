@@ -0,0 +1,53 @@
+use alloc::borrow::Cow;
+
+use crate::escape::{escape_html, unescape_html};
+
+/// Method-call forms of [`escape_html`] and [`unescape_html`], for chaining.
+/// The free functions remain the canonical API; these only delegate.
+///
+/// Implemented for `str`, so it works on `&str` and `String` alike:
+///
+/// ```
+/// use rysafe::HtmlEscapeExt;
+///
+/// let user_input = String::from("<b>Tom & Jerry</b>");
+/// assert_eq!(user_input.escape_html(), "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;");
+/// assert_eq!("&lt;b&gt;".unescape_html(), "<b>");
+/// ```
+pub trait HtmlEscapeExt {
+    /// Same as [`escape_html`].
+    fn escape_html(&self) -> Cow<'_, str>;
+
+    /// Same as [`unescape_html`].
+    fn unescape_html(&self) -> Cow<'_, str>;
+}
+
+impl HtmlEscapeExt for str {
+    fn escape_html(&self) -> Cow<'_, str> {
+        escape_html(self)
+    }
+
+    fn unescape_html(&self) -> Cow<'_, str> {
+        unescape_html(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn test_methods_match_free_functions() {
+        for text in ["", "plain", "<a href='x'>&amp;</a>", "AT&T"] {
+            assert_eq!(text.escape_html(), escape_html(text));
+            assert_eq!(text.unescape_html(), unescape_html(text));
+            assert_eq!(String::from(text).escape_html(), escape_html(text));
+        }
+    }
+
+    #[test]
+    fn test_escape_html_borrows_safe_input() {
+        assert!(matches!("plain".escape_html(), Cow::Borrowed("plain")));
+    }
+}
@@ -1,3 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+mod entities;
+pub mod error;
+pub mod escape;
+mod escaped;
+mod ext;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub mod core {
     pub use rysafe_core::*;
 }
+
+pub use error::{validate_utf8, EscapeError, EscapeResult, MAX_INPUT_SIZE};
+pub use escape::*;
+#[cfg(feature = "std")]
+pub use escaped::EscapeWriter;
+pub use escaped::{EscapedString, HtmlEscaped};
+pub use ext::HtmlEscapeExt;
+// Public only so the embedded-interpreter bench can register the module.
+#[cfg(feature = "python")]
+#[doc(hidden)]
+pub use python::_rysafe;
@@ -0,0 +1,112 @@
+//! The `rysafe._rysafe` extension module. `Markup` itself is a `str`
+//! subclass defined in `python/rysafe/__init__.py`; this module supplies the
+//! escaping it is built on.
+
+// PyO3 0.22's #[pyfunction] expansion trips this lint on every `PyResult` return.
+#![allow(clippy::useless_conversion)]
+
+use std::borrow::Cow;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyString};
+
+use crate::escape::{
+    escape_html, escape_html_bytes_cow, strip_tags, unescape_html, unescape_html_bytes,
+};
+
+// `bytes` and `bytearray` decoded as UTF-8; `None` for any other type.
+fn decode_bytes_like(obj: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+    let decoded = if let Ok(bytes) = obj.downcast::<PyBytes>() {
+        std::str::from_utf8(bytes.as_bytes()).map(str::to_owned)
+    } else if let Ok(bytes) = obj.downcast::<PyByteArray>() {
+        String::from_utf8(bytes.to_vec()).map_err(|err| err.utf8_error())
+    } else {
+        return Ok(None);
+    };
+    decoded
+        .map(Some)
+        .map_err(|err| PyValueError::new_err(format!("input is not valid UTF-8: {}", err)))
+}
+
+// The text of a `str`, or of `bytes`/`bytearray` holding UTF-8.
+fn text_of<'a>(s: &'a Bound<'_, PyAny>) -> PyResult<Cow<'a, str>> {
+    match decode_bytes_like(s)? {
+        Some(text) => Ok(Cow::Owned(text)),
+        None => s.downcast::<PyString>()?.to_cow(),
+    }
+}
+
+/// Escapes a `str`, or `bytes`/`bytearray` holding UTF-8, to a plain `str`;
+/// `rysafe.escape` wraps the result in `Markup`. Safe `str` input is returned
+/// as the same object.
+#[pyfunction(name = "_escape_inner")]
+fn escape_inner<'py>(s: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyString>> {
+    let text = text_of(s)?;
+    match escape_html(&text) {
+        Cow::Borrowed(_) if s.is_exact_instance_of::<PyString>() => {
+            Ok(s.downcast::<PyString>()?.clone())
+        }
+        escaped => Ok(PyString::new_bound(s.py(), &escaped)),
+    }
+}
+
+/// Backs `Markup.striptags`: drops comments and tags, collapses whitespace
+/// and unescapes.
+#[pyfunction(name = "_strip_tags")]
+fn strip_tags_inner(s: &str) -> String {
+    strip_tags(s)
+}
+
+/// Decodes references in a `str`, or in `bytes`/`bytearray` holding UTF-8.
+#[pyfunction]
+fn unescape(s: &Bound<'_, PyAny>) -> PyResult<String> {
+    Ok(unescape_html(&text_of(s)?).into_owned())
+}
+
+// The contents of a `bytes` or `bytearray`, undecoded.
+fn bytes_of<'a>(obj: &'a Bound<'_, PyAny>) -> PyResult<Cow<'a, [u8]>> {
+    if let Ok(bytes) = obj.downcast::<PyByteArray>() {
+        return Ok(Cow::Owned(bytes.to_vec()));
+    }
+    Ok(Cow::Borrowed(obj.downcast::<PyBytes>()?.as_bytes()))
+}
+
+/// Escapes `bytes` or `bytearray` without decoding them, returning `bytes`;
+/// invalid UTF-8 passes through. Safe `bytes` input is returned as the same
+/// object.
+#[pyfunction]
+fn escape_bytes<'py>(data: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let raw = bytes_of(data)?;
+    match escape_html_bytes_cow(&raw) {
+        Cow::Borrowed(_) if data.is_exact_instance_of::<PyBytes>() => {
+            Ok(data.downcast::<PyBytes>()?.clone())
+        }
+        escaped => Ok(PyBytes::new_bound(data.py(), &escaped)),
+    }
+}
+
+/// Decodes references in `bytes` or `bytearray`, returning `bytes`.
+#[pyfunction]
+fn unescape_bytes<'py>(data: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let raw = bytes_of(data)?;
+    Ok(PyBytes::new_bound(data.py(), &unescape_html_bytes(&raw)))
+}
+
+/// Bytes held by a `Markup` (or any `str`), object header included, as
+/// `str.__sizeof__` reports them.
+#[pyfunction]
+fn markup_memory_bytes(m: &Bound<'_, PyString>) -> PyResult<usize> {
+    m.call_method0("__sizeof__")?.extract()
+}
+
+#[pymodule]
+pub fn _rysafe(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(escape_inner, m)?)?;
+    m.add_function(wrap_pyfunction!(strip_tags_inner, m)?)?;
+    m.add_function(wrap_pyfunction!(unescape, m)?)?;
+    m.add_function(wrap_pyfunction!(escape_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(unescape_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(markup_memory_bytes, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! JavaScript bindings (with the `wasm` feature). Built with
+//! `wasm-pack build --no-default-features --features wasm`, the module
+//! exports `escapeHtml` and `unescapeHtml`, each taking and returning a JS
+//! string:
+//!
+//! ```js
+//! import { escapeHtml, unescapeHtml } from "rysafe";
+//!
+//! escapeHtml("<b>Tom & Jerry</b>"); // "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;"
+//! unescapeHtml("&lt;b&gt;");        // "<b>"
+//! ```
+
+use alloc::string::String;
+
+use wasm_bindgen::prelude::*;
+
+/// [`escape_html`](crate::escape_html) for JavaScript callers.
+#[wasm_bindgen(js_name = escapeHtml)]
+pub fn escape_html(input: &str) -> String {
+    crate::escape::escape_html(input).into_owned()
+}
+
+/// [`unescape_html`](crate::unescape_html) for JavaScript callers.
+#[wasm_bindgen(js_name = unescapeHtml)]
+pub fn unescape_html(input: &str) -> String {
+    crate::escape::unescape_html(input).into_owned()
+}
+
+// The exports are ordinary Rust functions off wasm32, so these run with a
+// plain `cargo test --features wasm`.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<b>Tom & 'Jerry'</b>"),
+            "&lt;b&gt;Tom &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+        assert_eq!(escape_html(""), "");
+    }
+
+    #[test]
+    fn test_unescape_html() {
+        assert_eq!(
+            unescape_html("&lt;b&gt; &amp;amp; &#x1F525;"),
+            "<b> &amp; 🔥"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let text = "日本 <script>\"x\"</script>";
+        assert_eq!(unescape_html(&escape_html(text)), text);
+    }
+}
+
+// The same exports called through the JavaScript boundary, under
+// `wasm-pack test --node --no-default-features --features wasm`.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[wasm_bindgen_test]
+    fn test_roundtrip() {
+        let text = "日本 <script>\"x\" & 'y'</script>";
+        let escaped = escape_html(text);
+        assert_eq!(
+            escaped,
+            "日本 &lt;script&gt;&#34;x&#34; &amp; &#39;y&#39;&lt;/script&gt;"
+        );
+        assert_eq!(unescape_html(&escaped), text);
+    }
+}
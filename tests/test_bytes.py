@@ -0,0 +1,80 @@
+from __future__ import annotations
+
+import pytest
+
+from rysafe import Markup
+from rysafe import escape
+from rysafe import escape_bytes
+from rysafe import unescape
+from rysafe import unescape_bytes
+
+
+@pytest.mark.parametrize(
+    ("value", "expect"),
+    (
+        (b"", b""),
+        (b"plain", b"plain"),
+        (b"<a & 'b'>", b"&lt;a &amp; &#39;b&#39;&gt;"),
+        ("日本<語>".encode(), "日本&lt;語&gt;".encode()),
+    ),
+)
+def test_escape_bytes(value, expect):
+    result = escape_bytes(value)
+    assert result == expect
+    assert type(result) is bytes
+
+
+def test_bytearray_input():
+    result = escape_bytes(bytearray(b"<a & b>"))
+    assert result == b"&lt;a &amp; b&gt;"
+    assert type(result) is bytes
+    assert type(escape_bytes(bytearray(b"plain"))) is bytes
+    result = unescape_bytes(bytearray(b"&lt;b&gt;"))
+    assert result == b"<b>"
+    assert type(result) is bytes
+
+
+@pytest.mark.parametrize("func", (escape_bytes, unescape_bytes))
+@pytest.mark.parametrize("value", ("<str>", 5, memoryview(b"<")))
+def test_bytes_functions_reject_other_types(func, value):
+    with pytest.raises(TypeError):
+        func(value)
+
+
+def test_invalid_utf8_passthrough():
+    assert escape_bytes(b"\xff<\xfe") == b"\xff&lt;\xfe"
+    assert unescape_bytes(b"\xff&amp;\xfe") == b"\xff&\xfe"
+
+
+def test_unescape_bytes():
+    result = unescape_bytes(b"&lt;b&gt; &#233;")
+    assert result == "<b> é".encode()
+    assert type(result) is bytes
+    assert unescape_bytes(escape_bytes(b"<\x80&\x00>")) == b"<\x80&\x00>"
+
+
+@pytest.mark.parametrize("wrap", (bytes, bytearray))
+def test_escape_decodes_utf8(wrap):
+    result = escape(wrap("日本 <a & 'b'>".encode()))
+    assert result == "日本 &lt;a &amp; &#39;b&#39;&gt;"
+    assert type(result) is Markup
+    assert escape(wrap(b"")) == ""
+
+
+@pytest.mark.parametrize("wrap", (bytes, bytearray))
+def test_unescape_decodes_utf8(wrap):
+    result = unescape(wrap("&lt;b&gt; &#233; 日本".encode()))
+    assert result == "<b> é 日本"
+    assert type(result) is str
+
+
+@pytest.mark.parametrize("func", (escape, unescape))
+@pytest.mark.parametrize("value", (b"\xff<", bytearray(b"a\xc3"), b"\xed\xa0\x80"))
+def test_invalid_utf8_raises_value_error(func, value):
+    with pytest.raises(ValueError, match="not valid UTF-8"):
+        func(value)
+
+
+def test_unescape_rejects_other_types():
+    with pytest.raises(TypeError):
+        unescape(42)
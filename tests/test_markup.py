@@ -0,0 +1,312 @@
+from __future__ import annotations
+
+import pytest
+
+from rysafe import Markup
+from rysafe import markup_memory_bytes
+
+
+def test_memory_bytes_scales_with_length() -> None:
+    small = markup_memory_bytes(Markup("a" * 10))
+    large = markup_memory_bytes(Markup("a" * 10_000))
+    assert small > 10
+    assert large - small >= 10_000 - 10
+
+
+def test_memory_bytes_matches_sizeof() -> None:
+    m = Markup("<b>hello</b>")
+    assert markup_memory_bytes(m) == m.__sizeof__()
+
+
+def test_markup_is_str() -> None:
+    import re
+
+    m = Markup("<b>x</b>")
+    assert isinstance(m, str)
+    assert "".join([m, "y"]) == "<b>x</b>y"
+    assert re.match(r"<b>", m) is not None
+    assert Markup(b"<\xc3\xa9>", "utf-8") == "<é>"
+
+
+@pytest.mark.parametrize(
+    ("value", "expect"),
+    (
+        ("<em>Foo &amp; Bar</em>", "Foo & Bar"),
+        ("<b>a  b</b> &amp; c", "a b & c"),
+        ("<!-- outer comment -->", ""),
+        ("<!-- outer <!-- inner --> comment -->", "comment -->"),
+        ("<!-- comment -->\n<p>text</p>", "text"),
+        ("<p>1 &lt; 2</p>\n<p>3 &gt; 2</p>", "1 < 2 3 > 2"),
+        ("<p\n class='x'>multi\nline</p>", "multi line"),
+        ("a < b", "a < b"),
+    ),
+)
+def test_striptags(value: str, expect: str) -> None:
+    result = Markup(value).striptags()
+    assert result == expect
+    assert type(result) is str
+
+
+def test_format_escapes_arguments() -> None:
+    result = Markup("<em>{}</em>").format("<script>")
+    assert isinstance(result, Markup)
+    assert result == Markup("<em>&lt;script&gt;</em>")
+
+
+def test_format_fields() -> None:
+    m = Markup("{0} {name} {1} {0}").format("&", "<", name="'")
+    assert m == "&amp; &#39; &lt; &amp;"
+    assert Markup("{{}} {{{}}}").format("<") == "{} {&lt;}"
+    assert Markup("{0[key]} {1.real}").format({"key": "<"}, 3) == "&lt; 3"
+
+
+def test_format_spec_and_conversion() -> None:
+    assert Markup("{:>5}|{!r}").format("&", "<b>") == "    &amp;|&#39;&lt;b&gt;&#39;"
+    assert Markup("{:{width}}|").format("<", width=3) == "&lt;  |"
+
+
+def test_format_keeps_markup() -> None:
+    assert Markup("<p>{}</p>").format(Markup("<b>bold</b>")) == "<p><b>bold</b></p>"
+
+
+@pytest.mark.parametrize("template", ("{", "}", "a { b", "{0", "x}"))
+def test_format_unmatched_braces(template: str) -> None:
+    with pytest.raises(ValueError):
+        Markup(template).format("x")
+
+
+def test_format_numbering_mix() -> None:
+    with pytest.raises(ValueError, match="cannot switch"):
+        Markup("{0}{}").format(1, 2)
+    with pytest.raises(ValueError, match="cannot switch"):
+        Markup("{}{0}").format(1, 2)
+
+
+def test_mod_escapes_operands() -> None:
+    result = Markup("<em>%s</em>") % "<script>"
+    assert type(result) is Markup
+    assert result == "<em>&lt;script&gt;</em>"
+    assert Markup("%s|%r") % ("&", "<b>") == "&amp;|&#39;&lt;b&gt;&#39;"
+    assert Markup("%(name)s %(n)d") % {"name": "'x'", "n": 3} == "&#39;x&#39; 3"
+    assert Markup("%s %.1f") % (Markup("<b>"), 2.25) == "<b> 2.2"
+    assert Markup("%s") % ("<a>",) == "&lt;a&gt;"
+
+
+def test_mod_single_mapping_and_tuple_operands() -> None:
+    assert Markup("%s") % {"a": "<"} == "{&#39;a&#39;: &#39;&lt;&#39;}"
+    assert Markup("%s") % (("<", ">"),) == "(&#39;&lt;&#39;, &#39;&gt;&#39;)"
+    with pytest.raises(TypeError):
+        Markup("%s %s") % "x"
+
+
+def test_join_escapes_plain_items() -> None:
+    result = Markup("<br>").join(["<a>", Markup("<b>ok</b>"), "x & y"])
+    assert isinstance(result, Markup)
+    assert result == "&lt;a&gt;<br><b>ok</b><br>x &amp; y"
+
+
+def test_join_iterables() -> None:
+    assert Markup(", ").join(str(i) for i in range(3)) == "0, 1, 2"
+    assert Markup("-").join([]) == ""
+
+
+def test_add_escapes_right_side() -> None:
+    result = Markup("<b>") + "<i>"
+    assert type(result) is Markup
+    assert result == "<b>&lt;i&gt;"
+    assert Markup("<b>") + Markup("<i>") == "<b><i>"
+
+
+def test_radd_escapes_left_side() -> None:
+    result = "<i>" + Markup("<b>")
+    assert type(result) is Markup
+    assert result == "&lt;i&gt;<b>"
+
+
+def test_add_unsupported_type() -> None:
+    with pytest.raises(TypeError):
+        Markup("x") + 1  # type: ignore[operator]
+    with pytest.raises(TypeError):
+        1 + Markup("x")  # type: ignore[operator]
+
+
+def test_mul_keeps_markup() -> None:
+    for result in (Markup("<b>") * 3, 3 * Markup("<b>")):
+        assert type(result) is Markup
+        assert result == "<b><b><b>"
+
+
+def test_mul_zero_and_negative() -> None:
+    assert Markup("x") * 0 == ""
+    assert type(Markup("x") * -2) is Markup
+    assert Markup("x") * -2 == ""
+
+
+def test_mul_huge_count_raises() -> None:
+    for count in (2**62, 2**63 - 1, 2**70):
+        with pytest.raises((OverflowError, MemoryError)):
+            Markup("xy") * count
+        with pytest.raises((OverflowError, MemoryError)):
+            count * Markup("xy")
+    assert Markup("") * 2**62 == ""
+
+
+def test_mul_unsupported_type() -> None:
+    with pytest.raises(TypeError):
+        Markup("x") * "y"  # type: ignore[operator]
+
+
+def test_format_html_format_hook() -> None:
+    class User:
+        def __init__(self, name: str) -> None:
+            self.name = name
+
+        def __html__(self) -> str:
+            return "<span>never used by format</span>"
+
+        def __html_format__(self, spec: str) -> Markup:
+            if spec == "link":
+                return Markup("<a href='/u'>{}</a>").format(self.name)
+            return Markup("<span>{}</span>").format(self.name)
+
+    user = User("<bob>")
+    assert Markup("{}").format(user) == "<span>&lt;bob&gt;</span>"
+    assert Markup("{:link}").format(user) == "<a href='/u'>&lt;bob&gt;</a>"
+
+
+def test_format_spec_on_html_without_hook() -> None:
+    class Html:
+        def __html__(self) -> str:
+            return "<b>"
+
+    assert Markup("{}").format(Html()) == "<b>"
+    with pytest.raises(ValueError):
+        Markup("{:>5}").format(Html())
+    with pytest.raises(ValueError, match="Unsupported format specification"):
+        Markup("{:>5}").format(Markup("<b>"))
+
+
+def test_getitem_keeps_markup() -> None:
+    m = Markup("<b>x</b>")
+    assert type(Markup("abc")[1:]) is Markup
+    assert m[0:3] == Markup("<b>")
+    assert type(m[0]) is Markup
+    assert m[-1] == ">"
+    assert m[::-1] == ">b/<x>b<"
+    with pytest.raises(IndexError):
+        m[100]
+
+
+@pytest.mark.parametrize(
+    ("method", "args", "expect"),
+    (
+        ("upper", (), "<B>&AMP;</B>"),
+        ("lower", (), "<b>&amp;</b>"),
+        ("title", (), "<B>&Amp;</B>"),
+        ("capitalize", (), "<b>&amp;</b>"),
+        ("strip", ("<>",), "b>&amp;</b"),
+        ("replace", ("&amp;", "<i>"), "<b>&lt;i&gt;</b>"),
+        ("center", (16,), "  <b>&amp;</b>  "),
+        ("zfill", (12,), "<b>&amp;</b>"),
+        ("removesuffix", ("</b>",), "<b>&amp;"),
+    ),
+)
+def test_str_methods_keep_markup(method: str, args: tuple, expect: str) -> None:
+    result = getattr(Markup("<b>&amp;</b>"), method)(*args)
+    assert type(result) is Markup
+    assert result == expect
+
+
+def test_str_methods_escape_arguments() -> None:
+    assert Markup("a b").replace(" ", Markup("<br>")) == "a<br>b"
+    assert Markup("a b").replace(" ", "<br>") == "a&lt;br&gt;b"
+    assert Markup("x").rjust(3, Markup("&")) == "&&x"
+    with pytest.raises(TypeError):
+        # MarkupSafe escapes the fill character too, so "&" is too long.
+        Markup("x").ljust(3, "&")
+
+
+def test_split_keeps_markup() -> None:
+    parts = Markup("<b>a</b> <i>b</i>").split()
+    assert parts == ["<b>a</b>", "<i>b</i>"]
+    assert all(type(part) is Markup for part in parts)
+    assert Markup("a&lt;b&lt;c").rsplit("&lt;", maxsplit=1) == ["a&lt;b", "c"]
+
+
+def test_partition_keeps_markup() -> None:
+    parts = Markup("a<b>c").partition("<b>")
+    assert type(parts) is tuple
+    assert parts == ("a", "<b>", "c")
+    assert all(type(part) is Markup for part in parts)
+    assert Markup("a&b&c").rpartition("&") == ("a&b", "&", "c")
+    assert Markup("abc").partition("x") == ("abc", "", "")
+    assert Markup("abc").rpartition("x") == ("", "", "abc")
+
+
+def test_splitlines_keeps_markup() -> None:
+    lines = Markup("<b>a</b>\n<i>b</i>\r\nc").splitlines()
+    assert lines == ["<b>a</b>", "<i>b</i>", "c"]
+    assert all(type(line) is Markup for line in lines)
+    assert Markup("a\nb").splitlines(True) == ["a\n", "b"]
+    assert Markup("a\nb").splitlines(keepends=True) == ["a\n", "b"]
+
+
+def test_classmethod_escape() -> None:
+    assert Markup.escape("<b>") == "&lt;b&gt;"
+    assert type(Markup.escape("<b>")) is Markup
+    m = Markup("Tom &amp; Jerry")
+    assert Markup.escape(m) == "Tom &amp; Jerry"
+    assert Markup.escape(Markup.escape(m)) == m
+
+
+def test_iter_yields_markup() -> None:
+    chars = list(Markup("<b>日&amp;"))
+    assert chars == ["<", "b", ">", "日", "&", "a", "m", "p", ";"]
+    assert all(type(ch) is Markup for ch in chars)
+    assert list(Markup()) == []
+
+
+@pytest.mark.parametrize(
+    ("value", "expect"),
+    (
+        ("x", "Markup('x')"),
+        ("a'b", 'Markup("a\'b")'),
+        ("&lt;\n", "Markup('&lt;\\n')"),
+    ),
+)
+def test_repr(value: str, expect: str) -> None:
+    assert repr(Markup(value)) == expect
+
+
+def test_contains_checks_stored_markup() -> None:
+    from rysafe import escape
+
+    assert "<" in Markup("<b>")
+    assert "<" not in escape("<b>")
+    assert "&lt;" in escape("<b>")
+    assert "&" in escape("&")
+    assert Markup("<") in Markup("a<b")
+    assert escape("<") not in Markup("a<b")
+    with pytest.raises(TypeError, match="requires string"):
+        1 in Markup("x")
+
+
+def test_fstring_format_spec() -> None:
+    padded = f"{Markup('<b>'):>10}"
+    assert padded == "       <b>"
+    assert type(padded) is str
+    assert f"{Markup('<b>')}" == "<b>"
+    assert f"{Markup('&lt;x'):.4}" == "&lt;"
+    assert format(Markup("ab"), "*^6") == "**ab**"
+    with pytest.raises(ValueError):
+        format(Markup("x"), "d")
+
+
+def test_html_returns_markup_itself() -> None:
+    from rysafe import escape
+
+    m = Markup("<b>")
+    assert type(m.__html__()) is Markup
+    assert m.__html__() is m
+    assert type(escape("<").__html__()) is Markup
+    assert escape(m) == "<b>"
@@ -0,0 +1,41 @@
+from __future__ import annotations
+
+import html
+
+import pytest
+
+from rysafe import escape
+from rysafe import unescape
+
+markupsafe = pytest.importorskip("markupsafe")
+
+VALUES = (
+    "",
+    "plain text",
+    "&<>\"'",
+    "<a href='/x?a=1&b=2'>\"Tom\" & 'Jerry'</a>",
+    "日本<語> 🔥 &amp;",
+)
+
+
+@pytest.mark.parametrize("value", VALUES)
+def test_escape_matches_markupsafe(value):
+    assert str(escape(value)) == str(markupsafe.escape(value))
+
+
+def test_apostrophe_entity():
+    assert str(escape("'")) == "&#39;" == str(markupsafe.escape("'"))
+
+
+@pytest.mark.parametrize("value", VALUES)
+def test_unescape_roundtrip(value):
+    escaped = str(escape(value))
+    assert unescape(escaped) == value == html.unescape(escaped)
+
+
+@pytest.mark.parametrize(
+    "value",
+    ("&lt;&gt;&amp;&quot;&apos;", "&#60;&#x3C;&#39;&#x27;", "&copy; &amp &notit; &bogus;", "&#128;&#xD800;"),
+)
+def test_unescape_matches_stdlib(value):
+    assert unescape(value) == html.unescape(value)
@@ -5,7 +5,9 @@ import typing as t
 import pytest
 
 from rysafe import escape
+from rysafe import escape_silent
 from rysafe import Markup
+from rysafe import soft_str
 
 
 @pytest.mark.parametrize(
@@ -65,4 +67,51 @@ def test_subclass() -> None:
     """Handle if str(o) does not return a plain str."""
     s = ReferenceStr("test")
     assert isinstance(s, str)
-    assert escape(s) == Markup("test")
\ No newline at end of file
+    assert escape(s) == Markup("test")
+
+
+def test_none() -> None:
+    assert escape(None) == Markup("None")
+    assert escape_silent(None) == Markup("")
+    assert escape_silent("<x>") == Markup("&lt;x&gt;")
+
+
+def test_empty() -> None:
+    class Empty:
+        def __str__(self) -> str:
+            return ""
+
+    assert escape("") == Markup("")
+    assert escape(Empty()) == Markup("")
+    assert escape_silent("") == Markup("")
+
+
+def test_str_raises() -> None:
+    class Broken:
+        def __str__(self) -> str:
+            raise RuntimeError("no str for you")
+
+    with pytest.raises(RuntimeError, match="no str for you"):
+        escape(Broken())
+    with pytest.raises(RuntimeError, match="no str for you"):
+        escape_silent(Broken())
+
+
+def test_html_raises() -> None:
+    class Broken:
+        def __html__(self) -> str:
+            raise KeyError("html")
+
+    with pytest.raises(KeyError):
+        escape(Broken())
+
+
+def test_soft_str() -> None:
+    assert soft_str(5) == "5"
+    assert type(soft_str(5)) is str
+    assert soft_str(None) == "None"
+    m = Markup("<b>")
+    assert soft_str(m) is m
+    assert isinstance(soft_str(Markup("x")), str)
+    s = "<plain>"
+    assert soft_str(s) is s
@@ -0,0 +1,27 @@
+from __future__ import annotations
+
+import pytest
+
+from rysafe import Markup
+from rysafe import unescape
+
+
+@pytest.mark.parametrize(
+    ("value", "expect"),
+    (
+        ("&lt;b&gt; &amp; &#34;q&#34; &#39;", "<b> & \"q\" '"),
+        ("&#x27;&#X27;&#39;&apos;", "''''"),
+        # double-encoded apostrophes decode exactly one layer
+        ("&amp;#x27;", "&#x27;"),
+        ("&amp;#39;", "&#39;"),
+        ("&amp;amp;#x27;", "&amp;#x27;"),
+        ("literal &#x27 text", "literal &#x27 text"),
+    ),
+)
+def test_unescape(value: str, expect: str) -> None:
+    assert unescape(value) == expect
+    assert Markup(value).unescape() == expect
+
+
+def test_markup_unescape_returns_str() -> None:
+    assert type(Markup("&lt;").unescape()) is str